@@ -0,0 +1,287 @@
+// Reading Gemfile.lock and Gemfile next to it from tar archives (plain
+// or gzipped). Only regular files are read
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use flate2::read::GzDecoder;
+use tar::Archive;
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[".tar", ".tar.gz", ".tgz"];
+const LOCK_FILE_NAME: &str = "Gemfile.lock";
+const GEMFILE_NAME: &str = "Gemfile";
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+///
+/// Splits path into archive path and path of entry inside archive, if
+/// some part of path is a tar archive. E.g. `app.tar.gz/srv/Gemfile.lock`
+/// is split into `app.tar.gz` and `srv/Gemfile.lock`
+///
+pub(crate) fn split_archive_path(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let archive = path
+        .ancestors()
+        .skip(1)
+        .find(|ancestor| is_archive(ancestor))?;
+    let entry = path.strip_prefix(archive).ok()?;
+
+    Some((archive.to_path_buf(), entry.to_path_buf()))
+}
+
+///
+/// Reads Gemfile.lock from archive. Without sub-path archive should
+/// contain single Gemfile.lock, otherwise entry should match sub-path
+///
+pub(crate) fn read_lockfile(archive: &Path, entry: &Path) -> Result<String> {
+    let entries = read_archive(archive)?;
+    let (_, content) = find_lockfile(&entries, archive, entry)?;
+
+    Ok(String::from_utf8_lossy(content).into_owned())
+}
+
+///
+/// Reads Gemfile located in the same archive directory as Gemfile.lock,
+/// selected by the same rules as in `read_lockfile`. Returns None, when
+/// archive has no such Gemfile
+///
+pub(crate) fn read_gemfile(archive: &Path, entry: &Path) -> Result<Option<String>> {
+    let entries = read_archive(archive)?;
+    let (lockfile, _) = find_lockfile(&entries, archive, entry)?;
+    let gemfile = Path::new(lockfile).with_file_name(GEMFILE_NAME);
+
+    Ok(entries
+        .iter()
+        .find(|(name, _)| Path::new(name) == gemfile)
+        .map(|(_, content)| String::from_utf8_lossy(content).into_owned()))
+}
+
+//
+// Reads all regular files of plain or gzipped tar archive
+//
+fn read_archive(archive: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let Ok(data) = fs::read(archive) else {
+        bail!("Could not read archive {}", archive.display());
+    };
+    let entries = match data.starts_with(&GZIP_MAGIC) {
+        true => read_entries(GzDecoder::new(data.as_slice())),
+        false => read_entries(data.as_slice()),
+    };
+
+    match entries {
+        Ok(entries) => Ok(entries),
+        Err(error) => bail!("Could not read archive {}: {}", archive.display(), error),
+    }
+}
+
+//
+// Finds Gemfile.lock entry. Without sub-path archive should contain
+// single Gemfile.lock, otherwise entry should match sub-path
+//
+fn find_lockfile<'a>(
+    entries: &'a [(String, Vec<u8>)],
+    archive: &Path,
+    entry: &Path,
+) -> Result<(&'a str, &'a [u8])> {
+    let lockfiles: Vec<&(String, Vec<u8>)> = entries
+        .iter()
+        .filter(|(name, _)| Path::new(name).file_name() == Some(LOCK_FILE_NAME.as_ref()))
+        .collect();
+
+    let selected: Vec<&(String, Vec<u8>)> = match entry == Path::new(LOCK_FILE_NAME) {
+        true => lockfiles,
+        false => lockfiles
+            .into_iter()
+            .filter(|(name, _)| Path::new(name) == entry)
+            .collect(),
+    };
+
+    match selected[..] {
+        [(name, content)] => Ok((name, content)),
+        [] => bail!(
+            "Archive {} does not contain {}",
+            archive.display(),
+            entry.display()
+        ),
+        _ => {
+            let names: Vec<&str> = selected.iter().map(|(name, _)| name.as_str()).collect();
+            bail!(
+                "Archive {} contains several Gemfile.lock files: {}. Select one with sub-path, e.g. {}",
+                archive.display(),
+                names.join(", "),
+                archive.join(names[0]).display()
+            )
+        }
+    }
+}
+
+//
+// Checks if path is a tar archive by its extension
+//
+fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+
+    ARCHIVE_EXTENSIONS
+        .iter()
+        .any(|extension| name.ends_with(extension))
+}
+
+//
+// Reads names and contents of regular files in tar archive. Names are
+// normalized without leading `./`
+//
+fn read_entries(data: impl Read) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut entries = Vec::new();
+
+    for entry in Archive::new(data).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let name = name.trim_start_matches("./").to_string();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        entries.push((name, content));
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //
+    // Builds ustar archive with given regular files
+    //
+    fn build_tar(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, content) in files {
+            let mut header = tar::Header::new_ustar();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+
+        builder.into_inner().unwrap()
+    }
+
+    fn write_archive(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        fs::write(&path, data).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_split_archive_path() {
+        assert_eq!(
+            split_archive_path(Path::new("/tmp/app.tar.gz/Gemfile.lock")),
+            Some((
+                PathBuf::from("/tmp/app.tar.gz"),
+                PathBuf::from("Gemfile.lock")
+            ))
+        );
+        assert_eq!(
+            split_archive_path(Path::new("app.tgz/srv/app/Gemfile.lock")),
+            Some((
+                PathBuf::from("app.tgz"),
+                PathBuf::from("srv/app/Gemfile.lock")
+            ))
+        );
+        assert_eq!(
+            split_archive_path(Path::new("/tmp/project/Gemfile.lock")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_entries() {
+        let data = build_tar(&[("./app/Gemfile.lock", "GEM\n"), ("app/README.md", "readme")]);
+
+        let entries = read_entries(data.as_slice()).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                (String::from("app/Gemfile.lock"), b"GEM\n".to_vec()),
+                (String::from("app/README.md"), b"readme".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_lockfile_from_gzipped_tar() {
+        let data = build_tar(&[("app/Gemfile.lock", "GEM\n  specs:\n    rake (13.0.6)\n")]);
+        let archive = write_archive("single.tar.gz", &crate::gzip::compress(&data));
+
+        let result = read_lockfile(&archive, Path::new("Gemfile.lock"));
+        fs::remove_file(&archive).unwrap();
+
+        assert_eq!(result.unwrap(), "GEM\n  specs:\n    rake (13.0.6)\n");
+    }
+
+    #[test]
+    fn test_read_lockfile_from_corrupted_archive() {
+        // gzip header with extra field length pointing past the end of data
+        let archive = write_archive(
+            "corrupted.tar.gz",
+            &[0x1f, 0x8b, 8, 0x04, 0, 0, 0, 0, 0, 0xff, 0xff],
+        );
+
+        let result = read_lockfile(&archive, Path::new("Gemfile.lock"));
+        fs::remove_file(&archive).unwrap();
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("Could not read archive"));
+    }
+
+    #[test]
+    fn test_read_lockfile_with_several_lockfiles() {
+        let data = build_tar(&[
+            ("billing/Gemfile.lock", "billing"),
+            ("auth/Gemfile.lock", "auth"),
+        ]);
+        let archive = write_archive("several.tar", &data);
+
+        let ambiguous = read_lockfile(&archive, Path::new("Gemfile.lock"));
+        let selected = read_lockfile(&archive, Path::new("auth/Gemfile.lock"));
+        let missing = read_lockfile(&archive, Path::new("api/Gemfile.lock"));
+        fs::remove_file(&archive).unwrap();
+
+        assert!(ambiguous.unwrap_err().to_string().contains(
+            "contains several Gemfile.lock files: billing/Gemfile.lock, auth/Gemfile.lock"
+        ));
+        assert_eq!(selected.unwrap(), "auth");
+        assert!(missing
+            .unwrap_err()
+            .to_string()
+            .contains("does not contain api/Gemfile.lock"));
+    }
+
+    #[test]
+    fn test_read_gemfile_next_to_lockfile() {
+        let data = build_tar(&[
+            ("billing/Gemfile.lock", "billing"),
+            ("billing/Gemfile", "gem 'rails'"),
+            ("auth/Gemfile.lock", "auth"),
+            ("Gemfile", "gem 'rake'"),
+        ]);
+        let archive = write_archive("gemfile.tar", &data);
+
+        let billing = read_gemfile(&archive, Path::new("billing/Gemfile.lock"));
+        let auth = read_gemfile(&archive, Path::new("auth/Gemfile.lock"));
+        let ambiguous = read_gemfile(&archive, Path::new("Gemfile.lock"));
+        fs::remove_file(&archive).unwrap();
+
+        assert_eq!(billing.unwrap().as_deref(), Some("gem 'rails'"));
+        assert_eq!(auth.unwrap(), None);
+        assert!(ambiguous.is_err());
+    }
+}
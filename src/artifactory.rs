@@ -0,0 +1,273 @@
+use async_trait::async_trait;
+use reqwest_middleware::ClientWithMiddleware;
+use serde_json::Value;
+use url::Url;
+
+use crate::client::{get_nexus_client, ClientOptions};
+use crate::errors::RepoError;
+use crate::gem::{get_file_name, Credentials, GemfileItem, Gemspec};
+use crate::repository::{RepoResult, RepositoryChecker};
+
+const ARTIFACTORY: &str = "Artifactory";
+
+pub(crate) struct Artifactory {
+    // Artifactory base url, e.g. https://mycompany.jfrog.io/artifactory
+    base_url: Url,
+
+    // Repositories to search in, all repositories if not set
+    repository: Option<String>,
+
+    // Artifactory client instance, it shares settings with Nexus client
+    client: ClientWithMiddleware,
+
+    // Access token or basic credentials for search API
+    credentials: Option<Credentials>,
+}
+
+impl Artifactory {
+    ///
+    /// Initializes new Artifactory instance
+    ///
+    pub(crate) fn new(
+        base_url: &str,
+        repository: Option<&str>,
+        client_options: &ClientOptions,
+    ) -> Result<Self, RepoError> {
+        let base_url = Url::parse(base_url)
+            .map_err(|_| RepoError::UrlParse(ARTIFACTORY, base_url.to_string()))?;
+        let client =
+            get_nexus_client(client_options).map_err(|_| RepoError::BuildClient(ARTIFACTORY))?;
+
+        Ok(Artifactory {
+            base_url,
+            repository: repository.map(str::to_string),
+            client,
+            credentials: None,
+        })
+    }
+
+    ///
+    /// Sets credentials, which are sent with every request
+    ///
+    pub(crate) fn with_credentials(mut self, credentials: Option<Credentials>) -> Self {
+        self.credentials = credentials;
+
+        self
+    }
+
+    //
+    // Parses quick search response, package exists if any artifact
+    // is found
+    //
+    fn check_response(
+        &self,
+        name: &str,
+        version: &str,
+        response: Result<Value, RepoError>,
+    ) -> Result<bool, RepoError> {
+        response.and_then(|json| {
+            json["results"]
+                .as_array()
+                .map(|results| !results.is_empty())
+                .ok_or_else(|| {
+                    RepoError::ParseResponse(ARTIFACTORY, name.to_string(), version.to_string())
+                })
+        })
+    }
+
+    //
+    // Sends request to Artifactory and try to receive response
+    //
+    async fn send_request(&self, gem_source: GemfileItem<'_>) -> Result<Value, RepoError> {
+        let (name, version, _) = gem_source;
+        let url = self.get_search_url(gem_source);
+        let request = self.client.get(&url);
+        let request = match &self.credentials {
+            Some(credentials) => credentials.authorize(request),
+            None => request,
+        };
+        let response = request.send().await.map_err(|_| {
+            RepoError::SendRequest(ARTIFACTORY, name.to_string(), version.to_string())
+        })?;
+
+        response.json::<Value>().await.map_err(|_| {
+            RepoError::ParseResponse(ARTIFACTORY, name.to_string(), version.to_string())
+        })
+    }
+
+    //
+    // Builds artifact quick search url for .gem file, file name of
+    // native gems includes platform
+    //
+    fn get_search_url(&self, gem_source: GemfileItem) -> String {
+        let mut url = self.base_url.clone();
+        let path = format!(
+            "{}/api/search/artifact",
+            self.base_url.path().trim_end_matches('/')
+        );
+
+        url.set_path(&path);
+        url.query_pairs_mut()
+            .append_pair("name", &get_file_name(gem_source));
+        if let Some(repository) = &self.repository {
+            url.query_pairs_mut().append_pair("repos", repository);
+        }
+
+        url.to_string()
+    }
+}
+
+#[async_trait]
+impl RepositoryChecker for Artifactory {
+    fn name(&self) -> &'static str {
+        ARTIFACTORY
+    }
+
+    async fn check_package(&self, package: &Gemspec) -> Result<RepoResult, RepoError> {
+        let Gemspec {
+            name,
+            version,
+            platform,
+            ..
+        } = &package;
+        let response = self
+            .send_request((name, version, platform.as_deref()))
+            .await;
+
+        let check_result = self.check_response(name, version, response);
+
+        check_result.map(|is_exist| RepoResult::new(package, is_exist))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_search_url() {
+        let artifactory = Artifactory::new(
+            "https://mycompany.jfrog.io/artifactory",
+            None,
+            &ClientOptions::default(),
+        )
+        .unwrap();
+
+        let url = artifactory.get_search_url(("rails", "7.1.1", None));
+
+        assert_eq!(
+            url,
+            "https://mycompany.jfrog.io/artifactory/api/search/artifact?name=rails-7.1.1.gem"
+        );
+    }
+
+    #[test]
+    fn test_build_search_url_with_repository() {
+        let artifactory = Artifactory::new(
+            "https://mycompany.jfrog.io/artifactory/",
+            Some("gems-local"),
+            &ClientOptions::default(),
+        )
+        .unwrap();
+
+        let url = artifactory.get_search_url(("rails", "7.1.1", None));
+
+        assert_eq!(url, "https://mycompany.jfrog.io/artifactory/api/search/artifact?name=rails-7.1.1.gem&repos=gems-local");
+    }
+
+    #[test]
+    fn test_build_search_url_with_platform() {
+        let artifactory = Artifactory::new(
+            "https://mycompany.jfrog.io/artifactory",
+            None,
+            &ClientOptions::default(),
+        )
+        .unwrap();
+
+        let url = artifactory.get_search_url(("nokogiri", "1.16.5", Some("x86_64-linux")));
+
+        assert_eq!(
+            url,
+            "https://mycompany.jfrog.io/artifactory/api/search/artifact?name=nokogiri-1.16.5-x86_64-linux.gem"
+        );
+    }
+
+    #[test]
+    fn test_check_response() {
+        let artifactory =
+            Artifactory::new("https://artifactory.local", None, &ClientOptions::default()).unwrap();
+        let found: Value = serde_json::from_str(
+            r#"{"results":[{"uri":"https://artifactory.local/api/storage/gems-local/gems/rails-7.1.1.gem"}]}"#,
+        )
+        .unwrap();
+        let absent: Value = serde_json::from_str(r#"{"results":[]}"#).unwrap();
+        let unexpected: Value = serde_json::from_str(r#"{"errors":[]}"#).unwrap();
+
+        assert!(artifactory
+            .check_response("rails", "7.1.1", Ok(found))
+            .unwrap());
+        assert!(!artifactory
+            .check_response("rails", "7.1.1", Ok(absent))
+            .unwrap());
+        assert_eq!(
+            artifactory
+                .check_response("rails", "7.1.1", Ok(unexpected))
+                .unwrap_err()
+                .to_string(),
+            "Could not parse Artifactory response for gem rails version 7.1.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_package() {
+        let server = crate::test_support::MockServer::new()
+            .route(
+                "/artifactory/api/search/artifact?name=rails-7.1.1.gem",
+                200,
+                r#"{"results":[]}"#,
+            )
+            .start();
+        let artifactory = Artifactory::new(
+            &format!("{}artifactory", server.url()),
+            None,
+            &ClientOptions::default(),
+        )
+        .unwrap()
+        .with_credentials(Some(Credentials::Token(String::from("secret"))));
+        let package = Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            ..Default::default()
+        };
+
+        let result = artifactory.check_package(&package).await.unwrap();
+        let request = server.requests()[0].to_lowercase();
+
+        assert!(result.is_absent());
+        assert!(request.contains("authorization: bearer secret\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_check_platform_package() {
+        let server = crate::test_support::MockServer::new()
+            .route(
+                "/api/search/artifact?name=nokogiri-1.16.5-x86_64-linux.gem",
+                200,
+                r#"{"results":[{"uri":"nokogiri-1.16.5-x86_64-linux.gem"}]}"#,
+            )
+            .start();
+        let artifactory =
+            Artifactory::new(server.url().as_str(), None, &ClientOptions::default()).unwrap();
+        let package = Gemspec {
+            name: String::from("nokogiri"),
+            version: String::from("1.16.5"),
+            platform: Some(String::from("x86_64-linux")),
+            ..Default::default()
+        };
+
+        let result = artifactory.check_package(&package).await.unwrap();
+
+        assert!(!result.is_absent());
+    }
+}
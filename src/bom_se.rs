@@ -1,14 +1,763 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+
 use anyhow::Result;
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use uuid::Uuid;
 
+use crate::bundler::Environment;
 use crate::config::Format;
-use crate::gem::Gemspec;
+use crate::gem::{Gemspec, Property};
 
 mod json;
+mod proto;
+mod validate;
 mod xml;
+mod yaml;
+
+pub(crate) use validate::validate;
+
+///
+/// Bom level information, which does not belong to any component
+///
+#[derive(Debug, Default)]
+struct BomInfo {
+    serial_number: String,
+    metadata: Option<Metadata>,
+    annotations: Vec<Annotation>,
+    // additional top level fields of json bom
+    extra_fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Default)]
+struct Metadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Tools>,
+    // project, which Gemfile.lock is described
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<RootComponent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<Property>,
+}
+
+///
+/// Project described by bom, it is set as metadata component
+///
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub(crate) struct RootComponent {
+    #[serde(rename = "type")]
+    pub(crate) component_type: ComponentType,
+    #[serde(rename = "bom-ref")]
+    pub(crate) bom_ref: String,
+    pub(crate) name: String,
+}
+
+///
+/// Type of gem components and metadata component
+///
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ComponentType {
+    Application,
+    #[default]
+    Library,
+    Framework,
+}
+
+///
+/// Bom level settings, which are not taken from components
+///
+#[derive(Debug, Default)]
+pub(crate) struct SerializeOptions {
+    // texts of bom level annotations
+    pub(crate) annotations: Vec<String>,
+    // Gemfile.lock environment, added as metadata
+    pub(crate) environment: Environment,
+    // additional top level fields of json bom
+    pub(crate) extra_fields: Vec<(String, String)>,
+    // project described by bom
+    pub(crate) root: Option<RootComponent>,
+    // bom creation time, used for metadata and annotations
+    pub(crate) timestamp: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Tools {
+    components: Vec<Tool>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Dependency {
+    #[serde(rename = "ref")]
+    reference: String,
+    #[serde(rename = "dependsOn", skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+}
+
+///
+/// Sorts components by name and version, so bom file content does not
+/// depend on the order in which rubygems.org responses were received.
+/// Components properties are sorted by name
+///
+pub(super) fn sort_components(gems: &mut [Gemspec]) {
+    gems.sort_by(|a, b| (&a.name, &a.version, &a.purl).cmp(&(&b.name, &b.version, &b.purl)));
+
+    for gem in gems.iter_mut() {
+        gem.properties.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+}
+
+///
+/// Merges components collected from one or several lockfiles. Components
+/// with the same purl are kept once, their hashes and evidence occurrences
+/// are joined. Platform specific builds have different purls and are kept
+///
+pub(super) fn merge_components(gems: Vec<Gemspec>) -> Vec<Gemspec> {
+    let mut merged: Vec<Gemspec> = Vec::new();
+
+    for gem in gems {
+        match merged.iter_mut().find(|known| known.purl == gem.purl) {
+            Some(known) => {
+                for hash in gem.hashes {
+                    if !known.hashes.contains(&hash) {
+                        known.hashes.push(hash);
+                    }
+                }
+
+                let occurrences = gem.evidence.map(|evidence| evidence.occurrences);
+                for occurrence in occurrences.unwrap_or_default() {
+                    known.add_occurrence(&occurrence.location);
+                }
+            }
+            None => merged.push(gem),
+        }
+    }
+
+    merged
+}
+
+///
+/// Collapses newlines and runs of spaces in components descriptions
+/// into single spaces
+///
+pub(super) fn normalize_descriptions(gems: &mut [Gemspec]) {
+    for gem in gems {
+        gem.description = gem
+            .description
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+    }
+}
+
+///
+/// Replaces descriptions matching given pattern with placeholder, so
+/// sensitive summaries of private gems are not shared
+///
+pub(super) fn redact_descriptions(gems: &mut [Gemspec], pattern: &Regex) {
+    for gem in gems
+        .iter_mut()
+        .filter(|gem| pattern.is_match(&gem.description))
+    {
+        gem.description = String::from(REDACTED_DESCRIPTION);
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Annotation {
+    subjects: Vec<String>,
+    annotator: Annotator,
+    timestamp: String,
+    text: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Annotator {
+    component: Tool,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Tool {
+    #[serde(rename = "type")]
+    component_type: String,
+    name: String,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
+}
+
+///
+/// Top level fields of CycloneDX bom, which could not be set as extra fields
+///
+pub(crate) const STANDARD_FIELDS: &[&str] = &[
+    "$schema",
+    "bomFormat",
+    "specVersion",
+    "serialNumber",
+    "version",
+    "metadata",
+    "components",
+    "services",
+    "externalReferences",
+    "dependencies",
+    "compositions",
+    "properties",
+    "vulnerabilities",
+    "annotations",
+    "formulation",
+    "definitions",
+    "signature",
+];
+
+const BUNDLER: &str = "bundler";
+const REDACTED_DESCRIPTION: &str = "[REDACTED]";
+const RUBY_VERSION_PROPERTY: &str = "cdx:bundler:ruby:version";
+
+//
+// Builds bom metadata from Gemfile.lock environment: bundler is listed
+// as a tool, Ruby version is added as property. Metadata is omitted, if
+// Gemfile.lock has no such information
+//
+fn build_metadata(environment: &Environment) -> Option<Metadata> {
+    let tools = environment.bundler_version.as_ref().map(|version| Tools {
+        components: vec![Tool {
+            component_type: String::from("application"),
+            name: String::from(BUNDLER),
+            version: version.clone(),
+            purl: Some(format!("pkg:gem/{BUNDLER}@{version}")),
+        }],
+    });
+    let properties: Vec<Property> = environment
+        .ruby_version
+        .iter()
+        .map(|version| Property::new(RUBY_VERSION_PROPERTY, version.clone()))
+        .collect();
+
+    if tools.is_none() && properties.is_empty() {
+        return None;
+    }
+
+    Some(Metadata {
+        tools,
+        properties,
+        ..Default::default()
+    })
+}
+
+//
+// Builds dependency graph of components. Every component is referenced by
+// its bom-ref, dependencies not present in components list are omitted.
+// Dependencies and their edges are sorted by reference
+//
+fn build_dependencies(gems: &[Gemspec]) -> Vec<Dependency> {
+    let mut dependencies: Vec<Dependency> = gems
+        .iter()
+        .map(|gem| {
+            let mut depends_on: Vec<String> = gems
+                .iter()
+                .filter(|dependency| gem.dependencies.contains(&dependency.name))
+                .map(|dependency| dependency.bom_ref.clone())
+                .collect();
+            depends_on.sort();
+
+            Dependency {
+                reference: gem.bom_ref.clone(),
+                depends_on,
+            }
+        })
+        .collect();
+    dependencies.sort_by(|a, b| a.reference.cmp(&b.reference));
+
+    dependencies
+}
+
+//
+// Builds bom level annotations with given texts. Annotations refer to the
+// bom itself by BOM-Link and are made by this tool
+//
+fn build_annotations(texts: &[String], serial_number: &str, timestamp: &str) -> Vec<Annotation> {
+    let bom_link = format!(
+        "urn:cdx:{}/1",
+        serial_number.trim_start_matches("urn:uuid:")
+    );
+
+    texts
+        .iter()
+        .map(|text| Annotation {
+            subjects: vec![bom_link.clone()],
+            annotator: Annotator {
+                component: Tool {
+                    component_type: String::from("application"),
+                    name: String::from(env!("CARGO_PKG_NAME")),
+                    version: String::from(env!("CARGO_PKG_VERSION")),
+                    purl: None,
+                },
+            },
+            timestamp: String::from(timestamp),
+            text: text.clone(),
+        })
+        .collect()
+}
+
+///
+/// Serializes gems collection to bom file content. Given annotations
+/// texts are added as bom level annotations, Gemfile.lock environment
+/// and root component are added as metadata. Extra fields are added as
+/// top level fields of json bom, xml and protobuf schemas do not allow
+/// that, so they are added as metadata properties
+///
+pub(super) fn serialize(
+    gems: &Vec<Gemspec>,
+    format: &Format,
+    options: &SerializeOptions,
+) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    serialize_to(gems, format, options, &mut content)?;
+
+    Ok(content)
+}
+
+///
+/// Serializes gems collection as `serialize` does, but writes bom file
+/// content to output. Json, xml and yaml are streamed without building
+/// whole document in memory, protobuf is written once built
+///
+pub(super) fn serialize_to(
+    gems: &Vec<Gemspec>,
+    format: &Format,
+    options: &SerializeOptions,
+    output: &mut dyn Write,
+) -> Result<()> {
+    let serial_number = format!("urn:uuid:{}", Uuid::new_v4());
+    let metadata = add_root_component(build_metadata(&options.environment), &options.root);
+    let mut info = BomInfo {
+        annotations: build_annotations(&options.annotations, &serial_number, &options.timestamp),
+        metadata: add_timestamp(metadata, &options.timestamp),
+        serial_number,
+        extra_fields: BTreeMap::new(),
+    };
 
-pub(super) fn serialize(gems: &Vec<Gemspec>, format: &Format) -> Result<String> {
     match format {
-        Format::Xml => xml::serialize(gems),
-        Format::Json => json::serialize(gems),
+        Format::Xml => {
+            info.metadata = add_metadata_properties(info.metadata, &options.extra_fields);
+            Ok(xml::write(gems, &info, output)?)
+        }
+        Format::Json => {
+            info.extra_fields = options.extra_fields.iter().cloned().collect();
+            json::write(gems, &info, output)
+        }
+        Format::Yaml => {
+            info.extra_fields = options.extra_fields.iter().cloned().collect();
+            yaml::write(gems, &info, output)
+        }
+        Format::Protobuf => {
+            info.metadata = add_metadata_properties(info.metadata, &options.extra_fields);
+            Ok(output.write_all(&proto::serialize(gems, &info)?)?)
+        }
+    }
+}
+
+//
+// Sets described project as metadata component, metadata is created
+// if needed
+//
+fn add_root_component(
+    metadata: Option<Metadata>,
+    root: &Option<RootComponent>,
+) -> Option<Metadata> {
+    let Some(root) = root else {
+        return metadata;
+    };
+
+    Some(Metadata {
+        component: Some(root.clone()),
+        ..metadata.unwrap_or_default()
+    })
+}
+
+//
+// Sets bom creation time in metadata, metadata is created if needed
+//
+fn add_timestamp(metadata: Option<Metadata>, timestamp: &str) -> Option<Metadata> {
+    if timestamp.is_empty() {
+        return metadata;
+    }
+
+    Some(Metadata {
+        timestamp: Some(String::from(timestamp)),
+        ..metadata.unwrap_or_default()
+    })
+}
+
+//
+// Adds properties to bom metadata, metadata is created if needed
+//
+fn add_metadata_properties(
+    metadata: Option<Metadata>,
+    fields: &[(String, String)],
+) -> Option<Metadata> {
+    if fields.is_empty() {
+        return metadata;
+    }
+
+    let mut metadata = metadata.unwrap_or_default();
+    metadata.properties.extend(
+        fields
+            .iter()
+            .map(|(name, value)| Property::new(name, value.clone())),
+    );
+
+    Some(metadata)
+}
+
+impl fmt::Display for ComponentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            ComponentType::Application => "application",
+            ComponentType::Library => "library",
+            ComponentType::Framework => "framework",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+impl BomInfo {
+    #[cfg(test)]
+    fn new(serial_number: &str) -> Self {
+        BomInfo {
+            serial_number: String::from(serial_number),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gem::HashSpec;
+
+    fn build_gem(name: &str, version: &str) -> Gemspec {
+        Gemspec {
+            name: name.to_string(),
+            version: version.to_string(),
+            purl: format!("pkg:gem/{name}@{version}"),
+            bom_ref: format!("pkg:gem/{name}@{version}"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sort_components() {
+        let mut gems = vec![
+            build_gem("rails", "7.1.1"),
+            build_gem("actioncable", "7.0.8.4"),
+            build_gem("rails", "7.0.8.4"),
+            build_gem("brakeman", "6.0.1"),
+        ];
+
+        sort_components(&mut gems);
+        let json = String::from_utf8(
+            serialize(&gems, &Format::Json, &SerializeOptions::default()).unwrap(),
+        )
+        .unwrap();
+
+        let positions: Vec<usize> = [
+            "pkg:gem/actioncable@7.0.8.4",
+            "pkg:gem/brakeman@6.0.1",
+            "pkg:gem/rails@7.0.8.4",
+            "pkg:gem/rails@7.1.1",
+        ]
+        .iter()
+        .map(|purl| json.find(purl).unwrap())
+        .collect();
+
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_stable_dependencies_and_properties() {
+        let build_gems = || {
+            vec![
+                Gemspec {
+                    properties: vec![
+                        Property::new("cdx:bundler:group", String::from("default")),
+                        Property::new("cdx:bundler:depth", String::from("1")),
+                    ],
+                    ..build_gem("rails", "7.1.1")
+                        .with_dependencies(&[String::from("rack"), String::from("actionpack")])
+                },
+                build_gem("rack", "3.0.0"),
+                build_gem("actionpack", "7.1.1"),
+            ]
+        };
+        let mut gems = build_gems();
+        let mut reversed = build_gems();
+        reversed.reverse();
+
+        sort_components(&mut gems);
+        sort_components(&mut reversed);
+
+        assert_eq!(build_dependencies(&gems), build_dependencies(&reversed));
+        assert_eq!(
+            build_dependencies(&gems)[2].depends_on,
+            vec!["pkg:gem/actionpack@7.1.1", "pkg:gem/rack@3.0.0"]
+        );
+        assert_eq!(gems[2].properties[0].name, "cdx:bundler:depth");
+        assert_eq!(gems[2].properties, reversed[2].properties);
+    }
+
+    #[test]
+    fn test_merge_components_occurrences() {
+        let mut rails = build_gem("rails", "7.1.1");
+        rails.add_occurrence("services/billing/Gemfile.lock");
+        let mut rails_again = build_gem("rails", "7.1.1");
+        rails_again.add_occurrence("services/auth/Gemfile.lock");
+        let mut rack = build_gem("rack", "3.0.0");
+        rack.add_occurrence("services/auth/Gemfile.lock");
+
+        let result = merge_components(vec![rails, rails_again, rack]);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            result[0]
+                .evidence
+                .as_ref()
+                .unwrap()
+                .occurrences
+                .iter()
+                .map(|occurrence| occurrence.location.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "services/billing/Gemfile.lock",
+                "services/auth/Gemfile.lock"
+            ]
+        );
+        assert_eq!(result[1].evidence.as_ref().unwrap().occurrences.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_components_hashes() {
+        let rails = Gemspec {
+            hashes: vec![HashSpec::with_alg("SHA-256", String::from("aaa"))],
+            ..build_gem("rails", "7.1.1")
+        };
+        let rails_again = Gemspec {
+            hashes: vec![
+                HashSpec::with_alg("SHA-256", String::from("aaa")),
+                HashSpec::with_alg("SHA-512", String::from("bbb")),
+            ],
+            ..build_gem("rails", "7.1.1")
+        };
+
+        let result = merge_components(vec![rails, rails_again]);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].hashes,
+            vec![
+                HashSpec::with_alg("SHA-256", String::from("aaa")),
+                HashSpec::with_alg("SHA-512", String::from("bbb")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_components_platforms() {
+        let nokogiri = build_gem("nokogiri", "1.16.5");
+        let nokogiri_linux = Gemspec {
+            purl: String::from("pkg:gem/nokogiri@1.16.5?platform=x86_64-linux"),
+            ..build_gem("nokogiri", "1.16.5")
+        };
+
+        let result = merge_components(vec![nokogiri, nokogiri_linux]);
+
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn test_build_metadata() {
+        let environment = Environment {
+            ruby_version: Some(String::from("ruby 3.3.0p0")),
+            bundler_version: Some(String::from("2.5.9")),
+        };
+
+        assert_eq!(build_metadata(&Environment::default()), None);
+        assert_eq!(
+            build_metadata(&environment),
+            Some(Metadata {
+                timestamp: None,
+                tools: Some(Tools {
+                    components: vec![Tool {
+                        component_type: String::from("application"),
+                        name: String::from("bundler"),
+                        version: String::from("2.5.9"),
+                        purl: Some(String::from("pkg:gem/bundler@2.5.9")),
+                    }],
+                }),
+                component: None,
+                properties: vec![Property::new(
+                    "cdx:bundler:ruby:version",
+                    String::from("ruby 3.3.0p0")
+                )],
+            })
+        );
+    }
+
+    #[test]
+    fn test_extra_fields_as_xml_properties() {
+        let options = SerializeOptions {
+            extra_fields: vec![(String::from("tenant"), String::from("payments"))],
+            ..Default::default()
+        };
+
+        let xml = String::from_utf8(serialize(&vec![], &Format::Xml, &options).unwrap()).unwrap();
+
+        assert!(xml.contains(r#"<property name="tenant">payments</property>"#));
+    }
+
+    #[test]
+    fn test_same_timestamp_in_formats() {
+        let options = SerializeOptions {
+            annotations: vec![String::from("Reviewed")],
+            timestamp: String::from("2023-10-11T19:31:51Z"),
+            ..Default::default()
+        };
+
+        let json = String::from_utf8(serialize(&vec![], &Format::Json, &options).unwrap()).unwrap();
+        let xml = String::from_utf8(serialize(&vec![], &Format::Xml, &options).unwrap()).unwrap();
+
+        assert_eq!(
+            json.matches(r#""timestamp": "2023-10-11T19:31:51Z""#)
+                .count(),
+            2
+        );
+        assert_eq!(
+            xml.matches("<timestamp>2023-10-11T19:31:51Z</timestamp>")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_root_component() {
+        let options = SerializeOptions {
+            root: Some(RootComponent {
+                component_type: ComponentType::Library,
+                bom_ref: String::from("my-gem"),
+                name: String::from("my-gem"),
+            }),
+            ..Default::default()
+        };
+
+        let json = String::from_utf8(serialize(&vec![], &Format::Json, &options).unwrap()).unwrap();
+        let xml = String::from_utf8(serialize(&vec![], &Format::Xml, &options).unwrap()).unwrap();
+
+        assert!(json.contains(
+            r#""component": {
+      "type": "library",
+      "bom-ref": "my-gem",
+      "name": "my-gem"
+    }"#
+        ));
+        assert!(xml.contains(
+            r#"<component type="library" bom-ref="my-gem">
+      <name>my-gem</name>
+    </component>"#
+        ));
+    }
+
+    #[test]
+    fn test_normalize_descriptions() {
+        let mut gems = vec![
+            Gemspec {
+                description: String::from("  Multi-line\n   description\twith   spaces \n"),
+                ..build_gem("rails", "7.1.1")
+            },
+            build_gem("rack", "3.0.0"),
+        ];
+
+        normalize_descriptions(&mut gems);
+
+        assert_eq!(gems[0].description, "Multi-line description with spaces");
+        assert_eq!(gems[1].description, "");
+    }
+
+    #[test]
+    fn test_redact_descriptions() {
+        let mut gems = vec![
+            Gemspec {
+                description: String::from("Client for internal billing.corp API"),
+                ..build_gem("billing-client", "1.0.0")
+            },
+            Gemspec {
+                description: String::from("Rack provides a minimal interface"),
+                ..build_gem("rack", "3.0.0")
+            },
+        ];
+
+        redact_descriptions(&mut gems, &Regex::new(r"\.corp\b").unwrap());
+
+        assert_eq!(gems[0].description, "[REDACTED]");
+        assert_eq!(gems[1].description, "Rack provides a minimal interface");
+    }
+
+    #[test]
+    fn test_build_dependencies() {
+        let gems = vec![
+            build_gem("actioncable", "7.0.8.4").with_dependencies(&[
+                String::from("actionpack"),
+                String::from("activesupport"),
+                String::from("nio4r"),
+            ]),
+            build_gem("actionpack", "7.0.8.4"),
+            build_gem("activesupport", "7.0.8.4"),
+        ];
+
+        let result = build_dependencies(&gems);
+
+        assert_eq!(
+            result,
+            vec![
+                Dependency {
+                    reference: String::from("pkg:gem/actioncable@7.0.8.4"),
+                    depends_on: vec![
+                        String::from("pkg:gem/actionpack@7.0.8.4"),
+                        String::from("pkg:gem/activesupport@7.0.8.4"),
+                    ],
+                },
+                Dependency {
+                    reference: String::from("pkg:gem/actionpack@7.0.8.4"),
+                    depends_on: vec![],
+                },
+                Dependency {
+                    reference: String::from("pkg:gem/activesupport@7.0.8.4"),
+                    depends_on: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_annotations() {
+        let result = build_annotations(
+            &[String::from("Release ticket REL-1234")],
+            "urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42",
+            "2024-05-13T15:24:55Z",
+        );
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].subjects,
+            vec!["urn:cdx:b83ca3d9-6b17-4566-bd50-201af63d9c42/1"]
+        );
+        assert_eq!(result[0].annotator.component.name, "cyclonedx-rs-gem");
+        assert_eq!(result[0].timestamp, "2024-05-13T15:24:55Z");
+        assert_eq!(result[0].text, "Release ticket REL-1234");
     }
 }
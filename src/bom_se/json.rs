@@ -1,46 +1,63 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
 use anyhow::Result;
 use serde::Serialize;
-use uuid::Uuid;
 
+use super::{build_dependencies, Annotation, BomInfo, Dependency, Metadata};
 use crate::gem::Gemspec;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct Bom<'a> {
+pub(super) struct Bom<'a> {
     bom_format: String,
     spec_version: String,
-    serial_number: String,
+    serial_number: &'a str,
     version: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<&'a Metadata>,
     components: &'a Vec<Gemspec>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<Dependency>,
+    #[serde(skip_serializing_if = "<[Annotation]>::is_empty")]
+    annotations: &'a [Annotation],
+    #[serde(flatten)]
+    extra_fields: &'a BTreeMap<String, String>,
 }
 
 ///
-/// Serialize gems collection to json string
+/// Writes gems collection as json directly to output, without building
+/// whole document in memory
 ///
-pub(super) fn serialize(gems: &Vec<Gemspec>) -> Result<String> {
-    let serial_number = format!("urn:uuid:{}", Uuid::new_v4());
+pub(super) fn write<W: Write>(gems: &Vec<Gemspec>, info: &BomInfo, mut output: W) -> Result<()> {
+    serde_json::to_writer_pretty(&mut output, &Bom::new(gems, info))?;
 
-    build_json(gems, serial_number)
+    Ok(output.flush()?)
 }
 
 //
-// Builds bom.json file content. A separate function need for
+// Builds bom.json file content as string. A separate function need for
 // testing
 //
-fn build_json(gems: &Vec<Gemspec>, serial_number: String) -> Result<String> {
-    let bom_content = Bom::new(gems, serial_number);
+#[cfg(test)]
+fn build_json(gems: &Vec<Gemspec>, info: &BomInfo) -> Result<String> {
+    let bom_content = Bom::new(gems, info);
 
     Ok(serde_json::to_string_pretty(&bom_content)?)
 }
 
 impl<'a> Bom<'a> {
-    fn new(components: &'a Vec<Gemspec>, serial_number: String) -> Bom<'a> {
+    pub(super) fn new(components: &'a Vec<Gemspec>, info: &'a BomInfo) -> Bom<'a> {
         Bom {
             bom_format: String::from("CycloneDX"),
             spec_version: String::from("1.5"),
-            serial_number,
+            serial_number: &info.serial_number,
             version: 1,
+            metadata: info.metadata.as_ref(),
             components,
+            dependencies: build_dependencies(components),
+            annotations: &info.annotations,
+            extra_fields: &info.extra_fields,
         }
     }
 }
@@ -48,15 +65,18 @@ impl<'a> Bom<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::gem::licenses::{KnownLicense, License, UnknownLicense};
-    use crate::gem::HashSpec;
+    use crate::bom_se::ComponentType;
+    use crate::gem::licenses::{
+        get_licenses, KnownLicense, License, LicenseExpression, UnknownLicense,
+    };
+    use crate::gem::{ExternalReference, HashSpec, Property, Scope};
 
     #[test]
     fn test_when_no_components() {
         let gems: Vec<Gemspec> = Vec::new();
         let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
 
-        let json = build_json(&gems, serial);
+        let json = build_json(&gems, &BomInfo::new(&serial));
         let expected = r#"{
   "bomFormat": "CycloneDX",
   "specVersion": "1.5",
@@ -67,25 +87,43 @@ mod tests {
         assert_eq!(json.unwrap(), expected);
     }
 
+    #[test]
+    fn test_extra_fields() {
+        let gems: Vec<Gemspec> = Vec::new();
+        let mut info = BomInfo::new("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+        info.extra_fields
+            .insert(String::from("tenantId"), String::from("payments"));
+
+        let json = build_json(&gems, &info).unwrap();
+
+        assert!(json.ends_with(
+            r#"  "components": [],
+  "tenantId": "payments"
+}"#
+        ));
+    }
+
     #[test]
     fn test_when_some_components() {
         let first_gem = Gemspec {
             name: String::from("activemodel"),
             version: String::from("7.0.8.4"),
             purl: String::from("pkg:gem/activemodel@7.0.8.4"),
+            bom_ref: String::from("pkg:gem/activemodel@7.0.8.4"),
             author: String::from("David Heinemeier Hansson"),
             licenses: vec![License::KnownLicense(KnownLicense::new(String::from("MIT")))],
             description: String::from("A toolkit for building modeling frameworks like Active Record. Rich support for attributes, callbacks, validations, serialization, internationalization, and testing."),
             hashes: vec!(HashSpec{
                 alg: String::from("SHA-256"),
                 content: String::from("ef4e092d8644121b3e756e831bed6a16878317d02b9611bec8efcfdaee6525d6")
-            })
-
+            }),
+            ..Default::default()
         };
         let second_gem = Gemspec {
             name: String::from("brakeman"),
             version: String::from("6.0.1"),
             purl: String::from("pkg:gem/brakeman@6.0.1"),
+            bom_ref: String::from("pkg:gem/brakeman@6.0.1"),
             author: String::from("Justin Collins"),
             licenses: vec![License::UnknownLicense(UnknownLicense::new(String::from(
                 "Brakeman Public Use License",
@@ -97,12 +135,13 @@ mod tests {
                     "39641c63bc247bbdf993a349de90a13e146c464c872191f2adc12555bde591be",
                 ),
             }],
+            ..Default::default()
         };
 
         let gems = vec![first_gem, second_gem];
         let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
 
-        let json = build_json(&gems, serial);
+        let json = build_json(&gems, &BomInfo::new(&serial));
         let expected = r#"{
   "bomFormat": "CycloneDX",
   "specVersion": "1.5",
@@ -110,13 +149,17 @@ mod tests {
   "version": 1,
   "components": [
     {
+      "type": "library",
+      "bom-ref": "pkg:gem/activemodel@7.0.8.4",
       "name": "activemodel",
       "version": "7.0.8.4",
       "purl": "pkg:gem/activemodel@7.0.8.4",
       "licenses": [
         {
-          "id": "MIT",
-          "properties": []
+          "license": {
+            "id": "MIT",
+            "properties": []
+          }
         }
       ],
       "author": "David Heinemeier Hansson",
@@ -129,13 +172,17 @@ mod tests {
       ]
     },
     {
+      "type": "library",
+      "bom-ref": "pkg:gem/brakeman@6.0.1",
       "name": "brakeman",
       "version": "6.0.1",
       "purl": "pkg:gem/brakeman@6.0.1",
       "licenses": [
         {
-          "name": "Brakeman Public Use License",
-          "properties": []
+          "license": {
+            "name": "Brakeman Public Use License",
+            "properties": []
+          }
         }
       ],
       "author": "Justin Collins",
@@ -147,8 +194,442 @@ mod tests {
         }
       ]
     }
+  ],
+  "dependencies": [
+    {
+      "ref": "pkg:gem/activemodel@7.0.8.4"
+    },
+    {
+      "ref": "pkg:gem/brakeman@6.0.1"
+    }
   ]
 }"#;
         assert_eq!(json.unwrap(), expected);
     }
+
+    #[test]
+    fn test_when_multiple_hashes() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            hashes: vec![
+                HashSpec::with_alg("SHA-256", String::from("66e736acc1d1ba5ca1b598fc8b6024715aee02025467bfa87682dbeb823ddc17")),
+                HashSpec::with_alg("SHA-512", String::from("1334f84a0a056646f698ca231171f3ff86324d63d684d8a0c13a6fe9dc61faaa0e8ef8acadd8c322567e8f8d2812711b2646fd27da33887be3d04f82a83a9cde")),
+            ],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "hashes": [
+        {
+          "alg": "SHA-256",
+          "content": "66e736acc1d1ba5ca1b598fc8b6024715aee02025467bfa87682dbeb823ddc17"
+        },
+        {
+          "alg": "SHA-512",
+          "content": "1334f84a0a056646f698ca231171f3ff86324d63d684d8a0c13a6fe9dc61faaa0e8ef8acadd8c322567e8f8d2812711b2646fd27da33887be3d04f82a83a9cde"
+        }
+      ]"#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_properties() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            properties: vec![Property::new("cdx:rubygems:stale", String::from("true"))],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "description": "",
+      "properties": [
+        {
+          "name": "cdx:rubygems:stale",
+          "value": "true"
+        }
+      ]"#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_external_references() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            external_references: vec![ExternalReference::new(
+                "distribution",
+                String::from("https://rubygems.org/gems/rails-7.1.1.gem"),
+            )],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "description": "",
+      "externalReferences": [
+        {
+          "type": "distribution",
+          "url": "https://rubygems.org/gems/rails-7.1.1.gem"
+        }
+      ]"#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_license_expression() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            licenses: vec![License::Expression(LicenseExpression::new(String::from(
+                "MIT OR Apache-2.0",
+            )))],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "licenses": [
+        {
+          "expression": "MIT OR Apache-2.0"
+        }
+      ],"#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_write_same_as_string() {
+        let gems = vec![Gemspec {
+            name: String::from("base64"),
+            version: String::from("0.3.0"),
+            purl: String::from("pkg:gem/base64@0.3.0"),
+            licenses: vec![License::KnownLicense(KnownLicense::new(String::from(
+                "Ruby",
+            )))],
+            properties: vec![Property::new("cdx:rubygems:stale", String::from("true"))],
+            ..Default::default()
+        }];
+        let info = BomInfo::new("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+        let mut output = Vec::new();
+
+        write(&gems, &info, &mut output).unwrap();
+
+        assert_eq!(output, build_json(&gems, &info).unwrap().into_bytes());
+    }
+
+    #[test]
+    fn test_when_multiple_licenses() {
+        let gems = vec![Gemspec {
+            name: String::from("base64"),
+            version: String::from("0.3.0"),
+            purl: String::from("pkg:gem/base64@0.3.0"),
+            licenses: vec![
+                License::KnownLicense(KnownLicense::new(String::from("Ruby"))),
+                License::UnknownLicense(UnknownLicense::new(String::from("Nonstandard"))),
+            ],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "licenses": [
+        {
+          "license": {
+            "id": "Ruby",
+            "properties": []
+          }
+        },
+        {
+          "license": {
+            "name": "Nonstandard",
+            "properties": []
+          }
+        }
+      ],"#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_license_expression_with_other_licenses() {
+        let gems = vec![Gemspec {
+            name: String::from("base64"),
+            version: String::from("0.3.0"),
+            purl: String::from("pkg:gem/base64@0.3.0"),
+            licenses: get_licenses(Some(vec![
+                String::from("Ruby"),
+                String::from("MIT OR Apache-2.0"),
+            ])),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "licenses": [
+        {
+          "expression": "Ruby AND (MIT OR Apache-2.0)"
+        }
+      ],"#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_scope() {
+        let gems = vec![Gemspec {
+            name: String::from("rack"),
+            version: String::from("3.0.8"),
+            purl: String::from("pkg:gem/rack@3.0.8"),
+            description: String::from("Rack"),
+            scope: Some(Scope::Optional),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "description": "Rack",
+      "scope": "optional""#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_type() {
+        let gems = vec![Gemspec {
+            component_type: ComponentType::Application,
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            bom_ref: String::from("pkg:gem/rails@7.1.1"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"    {
+      "type": "application",
+      "bom-ref": "pkg:gem/rails@7.1.1","#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_components_have_dependencies() {
+        let gems = vec![
+            Gemspec {
+                name: String::from("actioncable"),
+                version: String::from("7.0.8.4"),
+                purl: String::from("pkg:gem/actioncable@7.0.8.4"),
+                bom_ref: String::from("pkg:gem/actioncable@7.0.8.4"),
+                dependencies: vec![String::from("actionpack"), String::from("nio4r")],
+                ..Default::default()
+            },
+            Gemspec {
+                name: String::from("actionpack"),
+                version: String::from("7.0.8.4"),
+                purl: String::from("pkg:gem/actionpack@7.0.8.4"),
+                bom_ref: String::from("pkg:gem/actionpack@7.0.8.4"),
+                ..Default::default()
+            },
+        ];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"  "dependencies": [
+    {
+      "ref": "pkg:gem/actioncable@7.0.8.4",
+      "dependsOn": [
+        "pkg:gem/actionpack@7.0.8.4"
+      ]
+    },
+    {
+      "ref": "pkg:gem/actionpack@7.0.8.4"
+    }
+  ]"#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_bom_has_annotations() {
+        let gems: Vec<Gemspec> = Vec::new();
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+        let annotations = crate::bom_se::build_annotations(
+            &[String::from("Release ticket REL-1234")],
+            &serial,
+            "2024-05-13T15:24:55Z",
+        );
+
+        let json = build_json(
+            &gems,
+            &BomInfo {
+                annotations,
+                ..BomInfo::new(&serial)
+            },
+        )
+        .unwrap();
+        let expected = format!(
+            r#"  "annotations": [
+    {{
+      "subjects": [
+        "urn:cdx:b83ca3d9-6b17-4566-bd50-201af63d9c42/1"
+      ],
+      "annotator": {{
+        "component": {{
+          "type": "application",
+          "name": "cyclonedx-rs-gem",
+          "version": "{}"
+        }}
+      }},
+      "timestamp": "2024-05-13T15:24:55Z",
+      "text": "Release ticket REL-1234"
+    }}
+  ]"#,
+            env!("CARGO_PKG_VERSION")
+        );
+
+        assert!(json.contains(&expected));
+    }
+
+    #[test]
+    fn test_when_component_has_no_licenses() {
+        let gems = vec![Gemspec {
+            name: String::from("rake"),
+            version: String::from("13.0.6"),
+            purl: String::from("pkg:gem/rake@13.0.6"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert!(!json.contains("licenses"));
+    }
+
+    #[test]
+    fn test_when_component_has_group() {
+        let gems = vec![Gemspec {
+            group: Some(String::from("gems.mycompany.com")),
+            name: String::from("private_gem"),
+            version: String::from("1.0.0"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "group": "gems.mycompany.com",
+      "name": "private_gem","#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_no_hashes() {
+        let gems = vec![Gemspec {
+            name: String::from("annotate"),
+            version: String::from("3.2.0"),
+            purl: String::from("pkg:gem/annotate@3.2.0"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert!(!json.contains("hashes"));
+    }
+
+    #[test]
+    fn test_when_component_has_no_author() {
+        let gems = vec![Gemspec {
+            name: String::from("ruby_audit"),
+            version: String::from("2.3.0"),
+            purl: String::from("pkg:gem/ruby_audit@2.3.0"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert!(!json.contains("author"));
+        assert!(!json.contains("copyright"));
+    }
+
+    #[test]
+    fn test_when_bom_has_metadata() {
+        let gems: Vec<Gemspec> = Vec::new();
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+        let metadata = crate::bom_se::build_metadata(&crate::bundler::Environment {
+            ruby_version: Some(String::from("ruby 3.3.0p0")),
+            bundler_version: Some(String::from("2.5.9")),
+        });
+
+        let json = build_json(
+            &gems,
+            &BomInfo {
+                metadata,
+                ..BomInfo::new(&serial)
+            },
+        )
+        .unwrap();
+        let expected = r#"  "version": 1,
+  "metadata": {
+    "tools": {
+      "components": [
+        {
+          "type": "application",
+          "name": "bundler",
+          "version": "2.5.9",
+          "purl": "pkg:gem/bundler@2.5.9"
+        }
+      ]
+    },
+    "properties": [
+      {
+        "name": "cdx:bundler:ruby:version",
+        "value": "ruby 3.3.0p0"
+      }
+    ]
+  },
+  "components": []"#;
+
+        assert!(json.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_occurrences() {
+        let mut gem = Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            ..Default::default()
+        };
+        gem.add_occurrence("services/billing/Gemfile.lock");
+        gem.add_occurrence("services/auth/Gemfile.lock");
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let json = build_json(&vec![gem], &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      "evidence": {
+        "occurrences": [
+          {
+            "location": "services/billing/Gemfile.lock"
+          },
+          {
+            "location": "services/auth/Gemfile.lock"
+          }
+        ]
+      }"#;
+
+        assert!(json.contains(expected));
+    }
 }
@@ -0,0 +1,393 @@
+// Encoding of bom to CycloneDX 1.5 protobuf schema (bom-1.5.proto) with
+// prost messages
+
+use anyhow::Result;
+use prost::Message;
+use prost_types::Timestamp;
+
+use super::{build_dependencies, Annotation, BomInfo, ComponentType, Dependency, Metadata, Tool};
+use crate::gem::licenses::License;
+use crate::gem::{ExternalReference, Gemspec, HashSpec, Property, Scope};
+use crate::timestamp;
+
+mod cyclonedx;
+
+use cyclonedx::{
+    annotation, license, license_choice, Classification, ExternalReferenceType, HashAlg,
+};
+
+const SPEC_VERSION: &str = "1.5";
+
+///
+/// Serialize gems collection to CycloneDX protobuf message
+///
+pub(super) fn serialize(gems: &[Gemspec], info: &BomInfo) -> Result<Vec<u8>> {
+    let bom = cyclonedx::Bom {
+        spec_version: String::from(SPEC_VERSION),
+        version: Some(1),
+        serial_number: Some(info.serial_number.clone()),
+        metadata: info.metadata.as_ref().map(build_metadata),
+        components: gems.iter().map(build_component).collect(),
+        dependencies: build_dependencies(gems)
+            .iter()
+            .map(build_dependency)
+            .collect(),
+        annotations: info.annotations.iter().map(build_annotation).collect(),
+    };
+
+    Ok(bom.encode_to_vec())
+}
+
+//
+// Builds Metadata message
+//
+fn build_metadata(metadata: &Metadata) -> cyclonedx::Metadata {
+    cyclonedx::Metadata {
+        timestamp: metadata.timestamp.as_deref().and_then(build_timestamp),
+        tools: metadata.tools.as_ref().map(|tools| cyclonedx::Tool {
+            components: tools.components.iter().map(build_tool).collect(),
+        }),
+        component: metadata
+            .component
+            .as_ref()
+            .map(|root| cyclonedx::Component {
+                r#type: classification(root.component_type) as i32,
+                bom_ref: Some(root.bom_ref.clone()),
+                name: root.name.clone(),
+                ..Default::default()
+            }),
+        properties: metadata.properties.iter().map(build_property).collect(),
+    }
+}
+
+//
+// Timestamps are written with seconds precision, timestamp, which could
+// not be parsed, is omitted
+//
+fn build_timestamp(value: &str) -> Option<Timestamp> {
+    let seconds = timestamp::parse_rfc3339(value).ok()?;
+
+    Some(Timestamp {
+        seconds: seconds as i64,
+        nanos: 0,
+    })
+}
+
+//
+// Tools are encoded as components of Tool message
+//
+fn build_tool(tool: &Tool) -> cyclonedx::Component {
+    cyclonedx::Component {
+        r#type: Classification::Application as i32,
+        name: tool.name.clone(),
+        version: tool.version.clone(),
+        purl: tool.purl.clone(),
+        ..Default::default()
+    }
+}
+
+//
+// Builds Component message for gem
+//
+fn build_component(gem: &Gemspec) -> cyclonedx::Component {
+    cyclonedx::Component {
+        r#type: classification(gem.component_type) as i32,
+        bom_ref: non_empty(&gem.bom_ref),
+        author: non_empty(&gem.author),
+        group: gem.group.clone(),
+        name: gem.name.clone(),
+        version: gem.version.clone(),
+        description: non_empty(&gem.description),
+        scope: gem.scope.map(|scope| match scope {
+            Scope::Required => cyclonedx::Scope::Required as i32,
+            Scope::Optional => cyclonedx::Scope::Optional as i32,
+        }),
+        hashes: gem.hashes.iter().map(build_hash).collect(),
+        licenses: gem.licenses.iter().map(build_license).collect(),
+        copyright: gem.copyright.clone(),
+        purl: non_empty(&gem.purl),
+        external_references: gem
+            .external_references
+            .iter()
+            .map(build_external_reference)
+            .collect(),
+        properties: gem.properties.iter().map(build_property).collect(),
+        evidence: gem.evidence.as_ref().map(|evidence| cyclonedx::Evidence {
+            occurrences: evidence
+                .occurrences
+                .iter()
+                .map(|occurrence| cyclonedx::EvidenceOccurrences {
+                    location: occurrence.location.clone(),
+                })
+                .collect(),
+        }),
+    }
+}
+
+// Empty strings are not written for optional fields
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn classification(component_type: ComponentType) -> Classification {
+    match component_type {
+        ComponentType::Application => Classification::Application,
+        ComponentType::Library => Classification::Library,
+        ComponentType::Framework => Classification::Framework,
+    }
+}
+
+fn build_hash(hash: &HashSpec) -> cyclonedx::Hash {
+    let alg = match hash.alg.as_str() {
+        "MD5" => HashAlg::Md5,
+        "SHA-1" => HashAlg::Sha1,
+        "SHA-256" => HashAlg::Sha256,
+        "SHA-384" => HashAlg::Sha384,
+        "SHA-512" => HashAlg::Sha512,
+        "SHA3-256" => HashAlg::Sha3256,
+        "SHA3-384" => HashAlg::Sha3384,
+        "SHA3-512" => HashAlg::Sha3512,
+        "BLAKE2b-256" => HashAlg::Blake2b256,
+        "BLAKE2b-384" => HashAlg::Blake2b384,
+        "BLAKE2b-512" => HashAlg::Blake2b512,
+        "BLAKE3" => HashAlg::Blake3,
+        _ => HashAlg::Null,
+    };
+
+    cyclonedx::Hash {
+        alg: alg as i32,
+        value: hash.content.clone(),
+    }
+}
+
+//
+// Builds LicenseChoice message: license with id or name, or expression
+//
+fn build_license(license: &License) -> cyclonedx::LicenseChoice {
+    let choice = match license {
+        License::KnownLicense(license) => license_choice::Choice::License(cyclonedx::License {
+            license: Some(license::License::Id(license.id.clone())),
+        }),
+        License::UnknownLicense(license) => license_choice::Choice::License(cyclonedx::License {
+            license: Some(license::License::Name(license.name.clone())),
+        }),
+        License::Expression(license) => {
+            license_choice::Choice::Expression(license.expression.clone())
+        }
+    };
+
+    cyclonedx::LicenseChoice {
+        choice: Some(choice),
+    }
+}
+
+//
+// Unknown reference types are encoded as `other`
+//
+fn build_external_reference(reference: &ExternalReference) -> cyclonedx::ExternalReference {
+    let reference_type = match reference.reference_type.as_str() {
+        "vcs" => ExternalReferenceType::Vcs,
+        "issue-tracker" => ExternalReferenceType::IssueTracker,
+        "website" => ExternalReferenceType::Website,
+        "advisories" => ExternalReferenceType::Advisories,
+        "bom" => ExternalReferenceType::Bom,
+        "mailing-list" => ExternalReferenceType::MailingList,
+        "social" => ExternalReferenceType::Social,
+        "chat" => ExternalReferenceType::Chat,
+        "documentation" => ExternalReferenceType::Documentation,
+        "support" => ExternalReferenceType::Support,
+        "distribution" => ExternalReferenceType::Distribution,
+        "license" => ExternalReferenceType::License,
+        "build-meta" => ExternalReferenceType::BuildMeta,
+        "build-system" => ExternalReferenceType::BuildSystem,
+        "release-notes" => ExternalReferenceType::ReleaseNotes,
+        _ => ExternalReferenceType::Other,
+    };
+
+    cyclonedx::ExternalReference {
+        r#type: reference_type as i32,
+        url: reference.url.clone(),
+    }
+}
+
+fn build_property(property: &Property) -> cyclonedx::Property {
+    cyclonedx::Property {
+        name: property.name.clone(),
+        value: Some(property.value.clone()),
+    }
+}
+
+//
+// Dependencies of dependency are encoded as nested Dependency messages
+// with reference only
+//
+fn build_dependency(dependency: &Dependency) -> cyclonedx::Dependency {
+    cyclonedx::Dependency {
+        r#ref: dependency.reference.clone(),
+        dependencies: dependency
+            .depends_on
+            .iter()
+            .map(|reference| cyclonedx::Dependency {
+                r#ref: reference.clone(),
+                dependencies: vec![],
+            })
+            .collect(),
+    }
+}
+
+//
+// Annotator of bom level annotations is this tool
+//
+fn build_annotation(annotation: &Annotation) -> cyclonedx::Annotation {
+    cyclonedx::Annotation {
+        subjects: annotation.subjects.clone(),
+        annotator: Some(annotation::Annotator {
+            component: Some(build_tool(&annotation.annotator.component)),
+        }),
+        timestamp: build_timestamp(&annotation.timestamp),
+        text: annotation.text.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bom_se::build_annotations;
+    use crate::gem::licenses::{KnownLicense, LicenseExpression};
+
+    fn decode(gems: &[Gemspec], info: &BomInfo) -> cyclonedx::Bom {
+        cyclonedx::Bom::decode(serialize(gems, info).unwrap().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_components_decoded() {
+        let gems = vec![
+            Gemspec {
+                name: String::from("rails"),
+                version: String::from("7.1.1"),
+                purl: String::from("pkg:gem/rails@7.1.1"),
+                bom_ref: String::from("pkg:gem/rails@7.1.1"),
+                licenses: vec![License::KnownLicense(KnownLicense::new(String::from(
+                    "MIT",
+                )))],
+                hashes: vec![HashSpec {
+                    alg: String::from("SHA-256"),
+                    content: String::from("5ce4"),
+                }],
+                dependencies: vec![String::from("rake")],
+                scope: Some(Scope::Required),
+                ..Default::default()
+            },
+            Gemspec {
+                name: String::from("rake"),
+                version: String::from("13.0.6"),
+                purl: String::from("pkg:gem/rake@13.0.6"),
+                bom_ref: String::from("pkg:gem/rake@13.0.6"),
+                licenses: vec![License::Expression(LicenseExpression::new(String::from(
+                    "MIT OR Ruby",
+                )))],
+                ..Default::default()
+            },
+        ];
+        let serial = "urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42";
+
+        let bom = decode(&gems, &BomInfo::new(serial));
+
+        assert_eq!(bom.spec_version, "1.5");
+        assert_eq!(bom.serial_number.as_deref(), Some(serial));
+        let components = &bom.components;
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].r#type, Classification::Library as i32);
+        assert_eq!(components[0].name, "rails");
+        assert_eq!(components[0].version, "7.1.1");
+        assert_eq!(components[0].scope, Some(cyclonedx::Scope::Required as i32));
+        assert_eq!(components[1].scope, None);
+        assert_eq!(components[0].purl.as_deref(), Some("pkg:gem/rails@7.1.1"));
+        assert_eq!(
+            components[0].hashes,
+            [cyclonedx::Hash {
+                alg: HashAlg::Sha256 as i32,
+                value: String::from("5ce4"),
+            }]
+        );
+        assert_eq!(
+            components[0].licenses[0].choice,
+            Some(license_choice::Choice::License(cyclonedx::License {
+                license: Some(license::License::Id(String::from("MIT"))),
+            }))
+        );
+        assert_eq!(
+            components[1].licenses[0].choice,
+            Some(license_choice::Choice::Expression(String::from(
+                "MIT OR Ruby"
+            )))
+        );
+
+        let dependencies = &bom.dependencies;
+        assert_eq!(dependencies[0].r#ref, "pkg:gem/rails@7.1.1");
+        assert_eq!(dependencies[0].dependencies[0].r#ref, "pkg:gem/rake@13.0.6");
+    }
+
+    #[test]
+    fn test_annotations_and_evidence_decoded() {
+        let serial = "urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42";
+        let mut gem = Gemspec {
+            name: String::from("rake"),
+            ..Default::default()
+        };
+        gem.add_occurrence("Gemfile.lock");
+        let info = BomInfo {
+            annotations: build_annotations(
+                &[String::from("Reviewed")],
+                serial,
+                "2024-01-01T00:00:00Z",
+            ),
+            ..BomInfo::new(serial)
+        };
+
+        let bom = decode(&[gem], &info);
+
+        let annotation = &bom.annotations[0];
+        assert_eq!(annotation.text, "Reviewed");
+        assert_eq!(
+            annotation.subjects,
+            ["urn:cdx:b83ca3d9-6b17-4566-bd50-201af63d9c42/1"]
+        );
+        assert_eq!(
+            annotation.timestamp.as_ref().unwrap().seconds,
+            1_704_067_200
+        );
+        assert_eq!(
+            annotation
+                .annotator
+                .as_ref()
+                .unwrap()
+                .component
+                .as_ref()
+                .unwrap()
+                .name,
+            env!("CARGO_PKG_NAME")
+        );
+        assert_eq!(
+            bom.components[0].evidence.as_ref().unwrap().occurrences[0].location,
+            "Gemfile.lock"
+        );
+    }
+
+    #[test]
+    fn test_field_tags() {
+        let component = cyclonedx::Component {
+            evidence: Some(cyclonedx::Evidence::default()),
+            ..Default::default()
+        };
+        let bom = cyclonedx::Bom {
+            annotations: vec![cyclonedx::Annotation::default()],
+            ..Default::default()
+        };
+
+        // evidence is field 23 of Component, annotations are field 11 of Bom,
+        // both are length delimited
+        assert!(component.encode_to_vec().ends_with(&[0xba, 0x01, 0x00]));
+        assert_eq!(bom.encode_to_vec(), [0x5a, 0x00]);
+    }
+}
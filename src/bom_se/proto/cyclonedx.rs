@@ -0,0 +1,228 @@
+// Messages of CycloneDX 1.5 protobuf schema (bom-1.5.proto, package
+// cyclonedx.v1_5) in form of prost generated code. Only messages and
+// fields, which are written by this tool, are kept, field tags and enum
+// values are the same as in schema
+
+use prost_types::Timestamp;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Bom {
+    #[prost(string, tag = "1")]
+    pub(crate) spec_version: String,
+    #[prost(int32, optional, tag = "2")]
+    pub(crate) version: Option<i32>,
+    #[prost(string, optional, tag = "3")]
+    pub(crate) serial_number: Option<String>,
+    #[prost(message, optional, tag = "4")]
+    pub(crate) metadata: Option<Metadata>,
+    #[prost(message, repeated, tag = "5")]
+    pub(crate) components: Vec<Component>,
+    #[prost(message, repeated, tag = "8")]
+    pub(crate) dependencies: Vec<Dependency>,
+    #[prost(message, repeated, tag = "11")]
+    pub(crate) annotations: Vec<Annotation>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Metadata {
+    #[prost(message, optional, tag = "1")]
+    pub(crate) timestamp: Option<Timestamp>,
+    #[prost(message, optional, tag = "2")]
+    pub(crate) tools: Option<Tool>,
+    #[prost(message, optional, tag = "4")]
+    pub(crate) component: Option<Component>,
+    #[prost(message, repeated, tag = "8")]
+    pub(crate) properties: Vec<Property>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Tool {
+    #[prost(message, repeated, tag = "6")]
+    pub(crate) components: Vec<Component>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Component {
+    #[prost(enumeration = "Classification", tag = "1")]
+    pub(crate) r#type: i32,
+    #[prost(string, optional, tag = "3")]
+    pub(crate) bom_ref: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub(crate) author: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub(crate) group: Option<String>,
+    #[prost(string, tag = "8")]
+    pub(crate) name: String,
+    #[prost(string, tag = "9")]
+    pub(crate) version: String,
+    #[prost(string, optional, tag = "10")]
+    pub(crate) description: Option<String>,
+    #[prost(enumeration = "Scope", optional, tag = "11")]
+    pub(crate) scope: Option<i32>,
+    #[prost(message, repeated, tag = "12")]
+    pub(crate) hashes: Vec<Hash>,
+    #[prost(message, repeated, tag = "13")]
+    pub(crate) licenses: Vec<LicenseChoice>,
+    #[prost(string, optional, tag = "14")]
+    pub(crate) copyright: Option<String>,
+    #[prost(string, optional, tag = "16")]
+    pub(crate) purl: Option<String>,
+    #[prost(message, repeated, tag = "20")]
+    pub(crate) external_references: Vec<ExternalReference>,
+    #[prost(message, repeated, tag = "22")]
+    pub(crate) properties: Vec<Property>,
+    #[prost(message, optional, tag = "23")]
+    pub(crate) evidence: Option<Evidence>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Hash {
+    #[prost(enumeration = "HashAlg", tag = "1")]
+    pub(crate) alg: i32,
+    #[prost(string, tag = "2")]
+    pub(crate) value: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct LicenseChoice {
+    #[prost(oneof = "license_choice::Choice", tags = "1, 2")]
+    pub(crate) choice: Option<license_choice::Choice>,
+}
+
+pub(crate) mod license_choice {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub(crate) enum Choice {
+        #[prost(message, tag = "1")]
+        License(super::License),
+        #[prost(string, tag = "2")]
+        Expression(String),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct License {
+    #[prost(oneof = "license::License", tags = "1, 2")]
+    pub(crate) license: Option<license::License>,
+}
+
+pub(crate) mod license {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub(crate) enum License {
+        #[prost(string, tag = "1")]
+        Id(String),
+        #[prost(string, tag = "2")]
+        Name(String),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ExternalReference {
+    #[prost(enumeration = "ExternalReferenceType", tag = "1")]
+    pub(crate) r#type: i32,
+    #[prost(string, tag = "2")]
+    pub(crate) url: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Property {
+    #[prost(string, tag = "1")]
+    pub(crate) name: String,
+    #[prost(string, optional, tag = "2")]
+    pub(crate) value: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Dependency {
+    #[prost(string, tag = "1")]
+    pub(crate) r#ref: String,
+    #[prost(message, repeated, tag = "2")]
+    pub(crate) dependencies: Vec<Dependency>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Evidence {
+    #[prost(message, repeated, tag = "4")]
+    pub(crate) occurrences: Vec<EvidenceOccurrences>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct EvidenceOccurrences {
+    #[prost(string, tag = "2")]
+    pub(crate) location: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Annotation {
+    #[prost(string, repeated, tag = "2")]
+    pub(crate) subjects: Vec<String>,
+    #[prost(message, optional, tag = "3")]
+    pub(crate) annotator: Option<annotation::Annotator>,
+    #[prost(message, optional, tag = "4")]
+    pub(crate) timestamp: Option<Timestamp>,
+    #[prost(string, tag = "5")]
+    pub(crate) text: String,
+}
+
+pub(crate) mod annotation {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(crate) struct Annotator {
+        #[prost(message, optional, tag = "3")]
+        pub(crate) component: Option<super::Component>,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum Classification {
+    Null = 0,
+    Application = 1,
+    Framework = 2,
+    Library = 3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum Scope {
+    Unspecified = 0,
+    Required = 1,
+    Optional = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum HashAlg {
+    Null = 0,
+    Md5 = 1,
+    Sha1 = 2,
+    Sha256 = 3,
+    Sha384 = 4,
+    Sha512 = 5,
+    Sha3256 = 6,
+    Sha3384 = 7,
+    Sha3512 = 8,
+    Blake2b256 = 9,
+    Blake2b384 = 10,
+    Blake2b512 = 11,
+    Blake3 = 12,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum ExternalReferenceType {
+    Other = 0,
+    Vcs = 1,
+    IssueTracker = 2,
+    Website = 3,
+    Advisories = 4,
+    Bom = 5,
+    MailingList = 6,
+    Social = 7,
+    Chat = 8,
+    Documentation = 9,
+    Support = 10,
+    Distribution = 11,
+    License = 12,
+    BuildMeta = 13,
+    BuildSystem = 14,
+    ReleaseNotes = 15,
+}
@@ -0,0 +1,217 @@
+// Validation of json bom against CycloneDX 1.5 json schema. Schema is
+// embedded from https://cyclonedx.org/schema/bom-1.5.schema.json, it refers
+// to SPDX license ids and JSF signature schemas, which are registered as
+// local documents, so no requests are sent during validation
+
+use std::sync::LazyLock;
+
+use jsonschema::{Draft, JSONSchema};
+use serde_json::{json, Value};
+
+use crate::gem::licenses::LICENSES;
+
+const BOM_SCHEMA: &str = include_str!("schema/bom-1.5.schema.json");
+const SPDX_SCHEMA_ID: &str = "http://cyclonedx.org/schema/spdx.schema.json";
+const JSF_SCHEMA_ID: &str = "http://cyclonedx.org/schema/jsf-0.82.schema.json";
+
+static SCHEMA: LazyLock<JSONSchema> = LazyLock::new(|| {
+    let schema: Value = serde_json::from_str(BOM_SCHEMA).unwrap();
+
+    JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .with_document(String::from(SPDX_SCHEMA_ID), spdx_schema())
+        .with_document(String::from(JSF_SCHEMA_ID), jsf_schema())
+        .compile(&schema)
+        .unwrap()
+});
+
+///
+/// Validates json bom against CycloneDX 1.5 schema. Top level fields
+/// added by user (`--extra-bom-field`) are not part of schema, they are
+/// skipped. Returns list of violations, each one starts with path of
+/// wrong value
+///
+pub(crate) fn validate(content: &str, extra_fields: &[String]) -> Vec<String> {
+    let mut bom: Value = match serde_json::from_str(content) {
+        Ok(bom) => bom,
+        Err(error) => return vec![format!("/: not a valid json: {error}")],
+    };
+    if let Some(fields) = bom.as_object_mut() {
+        extra_fields.iter().for_each(|field| {
+            fields.remove(field);
+        });
+    }
+
+    let Err(errors) = SCHEMA.validate(&bom) else {
+        return vec![];
+    };
+
+    errors
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            let path = if path.is_empty() { "/" } else { &path };
+
+            format!("{path}: {error}")
+        })
+        .collect()
+}
+
+//
+// SPDX license ids schema is an enumeration of ids. Ids known by this
+// tool are used, licenses with other ids are written with name
+//
+fn spdx_schema() -> Value {
+    json!({
+        "$id": SPDX_SCHEMA_ID,
+        "type": "string",
+        "enum": LICENSES,
+    })
+}
+
+//
+// This tool does not sign boms, so signature is not restricted
+//
+fn jsf_schema() -> Value {
+    json!({
+        "$id": JSF_SCHEMA_ID,
+        "definitions": {
+            "signature": {
+                "type": "object",
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bom_se::{serialize, SerializeOptions};
+    use crate::config::Format;
+    use crate::gem::licenses::{KnownLicense, License, LicenseExpression, UnknownLicense};
+    use crate::gem::{ExternalReference, Gemspec, HashSpec, Property, Scope};
+
+    const VALID_BOM: &str = r#"{
+  "bomFormat": "CycloneDX",
+  "specVersion": "1.5",
+  "serialNumber": "urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42",
+  "version": 1,
+  "components": [
+    {
+      "type": "library",
+      "bom-ref": "pkg:gem/rake@13.0.6",
+      "name": "rake",
+      "version": "13.0.6",
+      "purl": "pkg:gem/rake@13.0.6",
+      "licenses": [{"license": {"id": "MIT"}}],
+      "hashes": [{"alg": "SHA-256", "content": "5ce4bf5037b4196c24ac62834d8db1ce175470391026bd9e557d669beeb19097"}],
+      "externalReferences": [{"type": "distribution", "url": "https://rubygems.org/gems/rake-13.0.6.gem"}]
+    }
+  ],
+  "dependencies": [{"ref": "pkg:gem/rake@13.0.6"}]
+}"#;
+
+    //
+    // Builds gems with all fields, which are written by serializer
+    //
+    fn build_gems() -> Vec<Gemspec> {
+        let mut rails = Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            bom_ref: String::from("pkg:gem/rails@7.1.1"),
+            author: String::from("David Heinemeier Hansson"),
+            description: String::from("Full-stack web application framework."),
+            licenses: vec![
+                License::KnownLicense(KnownLicense::new(String::from("MIT"))),
+                License::UnknownLicense(UnknownLicense::new(String::from("Nonstandard"))),
+            ],
+            hashes: vec![HashSpec {
+                alg: String::from("SHA-256"),
+                content: String::from(
+                    "5ce4bf5037b4196c24ac62834d8db1ce175470391026bd9e557d669beeb19097",
+                ),
+            }],
+            external_references: vec![ExternalReference {
+                reference_type: String::from("website"),
+                url: String::from("https://rubyonrails.org"),
+            }],
+            properties: vec![Property {
+                name: String::from("cdx:rubygems:released"),
+                value: String::from("2023-10-11T19:31:51Z"),
+            }],
+            scope: Some(Scope::Required),
+            dependencies: vec![String::from("rake")],
+            ..Default::default()
+        };
+        rails.add_occurrence("Gemfile.lock");
+        let rake = Gemspec {
+            name: String::from("rake"),
+            version: String::from("13.0.6"),
+            purl: String::from("pkg:gem/rake@13.0.6"),
+            bom_ref: String::from("pkg:gem/rake@13.0.6"),
+            licenses: vec![License::Expression(LicenseExpression::new(String::from(
+                "MIT OR Apache-2.0",
+            )))],
+            ..Default::default()
+        };
+
+        vec![rails, rake]
+    }
+
+    #[test]
+    fn test_valid_bom() {
+        assert_eq!(validate(VALID_BOM, &[]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_serialized_bom() {
+        let options = SerializeOptions {
+            annotations: vec![String::from("Reviewed")],
+            timestamp: String::from("2024-01-01T00:00:00Z"),
+            ..Default::default()
+        };
+
+        let bom = serialize(&build_gems(), &Format::Json, &options).unwrap();
+
+        assert_eq!(
+            validate(&String::from_utf8(bom).unwrap(), &[]),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_serialized_bom_with_extra_fields() {
+        let options = SerializeOptions {
+            extra_fields: vec![(String::from("tenantId"), String::from("payments"))],
+            timestamp: String::from("2024-01-01T00:00:00Z"),
+            ..Default::default()
+        };
+
+        let bom =
+            String::from_utf8(serialize(&build_gems(), &Format::Json, &options).unwrap()).unwrap();
+
+        assert_eq!(
+            validate(&bom, &[String::from("tenantId")]),
+            Vec::<String>::new()
+        );
+        assert_eq!(validate(&bom, &[]).len(), 1);
+    }
+
+    #[test]
+    fn test_corrupted_bom() {
+        let corrupted = VALID_BOM
+            .replace(r#""SHA-256""#, r#""SHA256""#)
+            .replace(r#""type": "library""#, r#""type": "gem""#);
+
+        let violations = validate(&corrupted, &[]);
+
+        assert_eq!(violations.len(), 2);
+        assert!(violations[0].starts_with("/components/0/type: "));
+        assert!(violations[1].starts_with("/components/0/hashes/0/alg: "));
+    }
+
+    #[test]
+    fn test_not_json() {
+        assert_eq!(validate("{", &[]).len(), 1);
+    }
+}
@@ -1,56 +1,134 @@
-use std::io::Result;
+use std::io::{Result, Write};
 
+use quick_xml::escape::escape;
 use quick_xml::events::{BytesPI, BytesText, Event};
 use quick_xml::writer::Writer;
-use uuid::Uuid;
 
+use super::{build_dependencies, Annotation, BomInfo, Metadata};
 use crate::gem::licenses::License;
-use crate::gem::Gemspec;
+use crate::gem::{Evidence, Gemspec, Property};
 
 ///
-/// Serialize gems collection to xml string
+/// Writes gems collection as xml directly to output, without building
+/// whole document in memory
 ///
-pub(super) fn serialize(gems: &Vec<Gemspec>) -> anyhow::Result<String> {
-    let random_uuid = Uuid::new_v4();
-    let serial_number = format!("urn:uuid:{}", random_uuid);
+pub(super) fn write<W: Write>(gems: &Vec<Gemspec>, info: &BomInfo, output: W) -> Result<()> {
+    let mut writer = Writer::new_with_indent(output, b' ', 2);
 
-    build_xml(gems, &serial_number)
+    let pi = r#"xml version="1.0" encoding="utf-8""#;
+
+    writer.write_event(Event::PI(BytesPI::new(pi)))?;
+
+    writer
+        .create_element("bom")
+        .with_attributes(vec![
+            ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+            ("xmlns:xsd", "http://www.w3.org/2001/XMLSchema"),
+            ("serialNumber", info.serial_number.as_str()),
+            ("version", "1"),
+            ("xmlns", "http://cyclonedx.org/schema/bom/1.5"),
+        ])
+        .write_inner_content(|writer| {
+            if let Some(metadata) = &info.metadata {
+                build_metadata(writer, metadata)?;
+            }
+            build_components(writer, gems)?;
+            build_dependencies_graph(writer, gems)?;
+            build_annotations(writer, &info.annotations)
+        })?;
+
+    writer.into_inner().flush()
 }
 
 //
-// Builds bom.xml content
+// Builds bom.xml content as string, used in tests
 //
-fn build_xml(gems: &Vec<Gemspec>, serial_number: &str) -> anyhow::Result<String> {
+#[cfg(test)]
+fn build_xml(gems: &Vec<Gemspec>, info: &BomInfo) -> anyhow::Result<String> {
     let mut buffer = Vec::new();
-    let mut writer = Writer::new_with_indent(&mut buffer, b' ', 2);
-
-    let pi = r#"xml version="1.0" encoding="utf-8""#;
+    write(gems, info, &mut buffer)?;
 
-    writer.write_event(Event::PI(BytesPI::new(pi)))?;
+    Ok(String::from_utf8(buffer)?)
+}
 
+//
+// Builds xml repersentatiom of "metadata" tag
+//
+fn build_metadata<W: Write>(writer: &mut Writer<W>, metadata: &Metadata) -> Result<()> {
     writer
-        .create_element("bom")
-        .with_attributes(
-            vec![
-                ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
-                ("xmlns:xsd", "http://www.w3.org/2001/XMLSchema"),
-                ("serialNumber", serial_number),
-                ("version", "1"),
-                ("xmlns", "http://cyclonedx.org/schema/bom/1.5"),
-            ]
-            .into_iter(),
-        )
-        .write_inner_content(|writer| build_components(writer, gems))?;
+        .create_element("metadata")
+        .write_inner_content(|writer| {
+            if let Some(timestamp) = &metadata.timestamp {
+                writer
+                    .create_element("timestamp")
+                    .write_text_content(text_content(timestamp))?;
+            }
+
+            if let Some(tools) = &metadata.tools {
+                writer
+                    .create_element("tools")
+                    .write_inner_content(|writer| {
+                        writer
+                            .create_element("components")
+                            .write_inner_content(|writer| {
+                                for tool in &tools.components {
+                                    writer
+                                        .create_element("component")
+                                        .with_attribute(("type", tool.component_type.as_str()))
+                                        .write_inner_content(|writer| {
+                                            writer
+                                                .create_element("name")
+                                                .write_text_content(text_content(&tool.name))?;
+                                            writer
+                                                .create_element("version")
+                                                .write_text_content(text_content(&tool.version))?;
+                                            if let Some(purl) = &tool.purl {
+                                                writer
+                                                    .create_element("purl")
+                                                    .write_text_content(text_content(purl))?;
+                                            }
+
+                                            Ok(())
+                                        })?;
+                                }
+
+                                Ok(())
+                            })?;
 
-    let xml_bytes = writer.into_inner();
+                        Ok(())
+                    })?;
+            }
 
-    Ok(String::from_utf8(xml_bytes.to_vec())?)
+            if let Some(root) = &metadata.component {
+                writer
+                    .create_element("component")
+                    .with_attribute(("type", root.component_type.to_string().as_str()))
+                    .with_attribute(("bom-ref", root.bom_ref.as_str()))
+                    .write_inner_content(|writer| {
+                        writer
+                            .create_element("name")
+                            .write_text_content(text_content(&root.name))?;
+
+                        Ok(())
+                    })?;
+            }
+
+            if !metadata.properties.is_empty() {
+                writer
+                    .create_element("properties")
+                    .write_inner_content(|writer| build_properties(writer, &metadata.properties))?;
+            }
+
+            Ok(())
+        })?;
+
+    Ok(())
 }
 
 //
 // Builds xml repersentatiom of "components" tag. It represents all dependencies
 //
-fn build_components(writer: &mut Writer<&mut Vec<u8>>, gems: &Vec<Gemspec>) -> Result<()> {
+fn build_components<W: Write>(writer: &mut Writer<W>, gems: &Vec<Gemspec>) -> Result<()> {
     writer
         .create_element("components")
         .write_inner_content(|writer| {
@@ -63,38 +141,221 @@ fn build_components(writer: &mut Writer<&mut Vec<u8>>, gems: &Vec<Gemspec>) -> R
     Ok(())
 }
 
+//
+// Builds xml repersentatiom of "dependencies" tag. It contains dependency
+// graph of components, referenced by purl
+//
+fn build_dependencies_graph<W: Write>(writer: &mut Writer<W>, gems: &[Gemspec]) -> Result<()> {
+    if gems.is_empty() {
+        return Ok(());
+    }
+
+    writer
+        .create_element("dependencies")
+        .write_inner_content(|writer| {
+            for dependency in build_dependencies(gems) {
+                let element = writer
+                    .create_element("dependency")
+                    .with_attribute(("ref", dependency.reference.as_str()));
+
+                if dependency.depends_on.is_empty() {
+                    element.write_empty()?;
+                    continue;
+                }
+
+                element.write_inner_content(|writer| {
+                    for reference in &dependency.depends_on {
+                        writer
+                            .create_element("dependency")
+                            .with_attribute(("ref", reference.as_str()))
+                            .write_empty()?;
+                    }
+
+                    Ok(())
+                })?;
+            }
+
+            Ok(())
+        })?;
+
+    Ok(())
+}
+
+//
+// Builds xml repersentatiom of "annotations" tag
+//
+fn build_annotations<W: Write>(writer: &mut Writer<W>, annotations: &[Annotation]) -> Result<()> {
+    if annotations.is_empty() {
+        return Ok(());
+    }
+
+    writer
+        .create_element("annotations")
+        .write_inner_content(|writer| {
+            for annotation in annotations {
+                writer
+                    .create_element("annotation")
+                    .write_inner_content(|writer| {
+                        writer
+                            .create_element("subjects")
+                            .write_inner_content(|writer| {
+                                for subject in &annotation.subjects {
+                                    writer
+                                        .create_element("subject")
+                                        .with_attribute(("ref", subject.as_str()))
+                                        .write_empty()?;
+                                }
+
+                                Ok(())
+                            })?;
+
+                        let tool = &annotation.annotator.component;
+                        writer
+                            .create_element("annotator")
+                            .write_inner_content(|writer| {
+                                writer
+                                    .create_element("component")
+                                    .with_attribute(("type", tool.component_type.as_str()))
+                                    .write_inner_content(|writer| {
+                                        writer
+                                            .create_element("name")
+                                            .write_text_content(text_content(&tool.name))?;
+                                        writer
+                                            .create_element("version")
+                                            .write_text_content(text_content(&tool.version))?;
+
+                                        Ok(())
+                                    })?;
+
+                                Ok(())
+                            })?;
+
+                        writer
+                            .create_element("timestamp")
+                            .write_text_content(text_content(&annotation.timestamp))?;
+                        writer
+                            .create_element("text")
+                            .write_text_content(text_content(&annotation.text))?;
+
+                        Ok(())
+                    })?;
+            }
+
+            Ok(())
+        })?;
+
+    Ok(())
+}
+
 //
 // Builds xml repersentatiom of "component" tag. It is main part of bom.xml,
 // becase it represents one dependency
 //
-fn build_component(writer: &mut Writer<&mut Vec<u8>>, gem: &Gemspec) -> Result<()> {
+fn build_component<W: Write>(writer: &mut Writer<W>, gem: &Gemspec) -> Result<()> {
+    let component_type = gem.component_type.to_string();
+    let mut attributes = vec![("type", component_type.as_str())];
+    if !gem.bom_ref.is_empty() {
+        attributes.push(("bom-ref", gem.bom_ref.as_str()));
+    }
+
     writer
         .create_element("component")
-        .with_attribute(("type", "library"))
+        .with_attributes(attributes)
         .write_inner_content(|writer| {
+            if !gem.author.is_empty() {
+                writer
+                    .create_element("author")
+                    .write_text_content(text_content(&gem.author))?;
+            }
+
+            if let Some(group) = &gem.group {
+                writer
+                    .create_element("group")
+                    .write_text_content(text_content(group))?;
+            }
+
             writer
                 .create_element("name")
-                .write_text_content(BytesText::new(&gem.name))?;
+                .write_text_content(text_content(&gem.name))?;
 
             writer
                 .create_element("version")
-                .write_text_content(BytesText::new(&gem.version))?;
+                .write_text_content(text_content(&gem.version))?;
 
             writer
                 .create_element("description")
-                .write_text_content(BytesText::new(&gem.description))?;
+                .write_text_content(text_content(&gem.description))?;
 
-            writer
-                .create_element("hashes")
-                .write_inner_content(|writer| build_hashes(writer, gem))?;
+            if let Some(scope) = gem.scope {
+                writer
+                    .create_element("scope")
+                    .write_text_content(BytesText::new(scope.as_str()))?;
+            }
 
-            writer
-                .create_element("licenses")
-                .write_inner_content(|writer| build_licanses(writer, gem))?;
+            if !gem.hashes.is_empty() {
+                writer
+                    .create_element("hashes")
+                    .write_inner_content(|writer| build_hashes(writer, gem))?;
+            }
+
+            if !gem.licenses.is_empty() {
+                writer
+                    .create_element("licenses")
+                    .write_inner_content(|writer| build_licanses(writer, gem))?;
+            }
+
+            if let Some(copyright) = &gem.copyright {
+                writer
+                    .create_element("copyright")
+                    .write_text_content(text_content(copyright))?;
+            }
 
             writer
                 .create_element("purl")
-                .write_text_content(BytesText::new(&gem.purl))?;
+                .write_text_content(text_content(&gem.purl))?;
+
+            if !gem.external_references.is_empty() {
+                writer
+                    .create_element("externalReferences")
+                    .write_inner_content(|writer| build_references(writer, gem))?;
+            }
+
+            if !gem.properties.is_empty() {
+                writer
+                    .create_element("properties")
+                    .write_inner_content(|writer| build_properties(writer, &gem.properties))?;
+            }
+
+            if let Some(evidence) = &gem.evidence {
+                writer
+                    .create_element("evidence")
+                    .write_inner_content(|writer| build_occurrences(writer, evidence))?;
+            }
+
+            Ok(())
+        })?;
+
+    Ok(())
+}
+
+//
+// Builds xml repersentatiom of "occurrences" child tag for "evidence" tag
+//
+fn build_occurrences<W: Write>(writer: &mut Writer<W>, evidence: &Evidence) -> Result<()> {
+    writer
+        .create_element("occurrences")
+        .write_inner_content(|writer| {
+            for occurrence in &evidence.occurrences {
+                writer
+                    .create_element("occurrence")
+                    .write_inner_content(|writer| {
+                        writer
+                            .create_element("location")
+                            .write_text_content(text_content(&occurrence.location))?;
+
+                        Ok(())
+                    })?;
+            }
 
             Ok(())
         })?;
@@ -105,12 +366,12 @@ fn build_component(writer: &mut Writer<&mut Vec<u8>>, gem: &Gemspec) -> Result<(
 //
 // Builds xml repersentatiom of "hash" child tag for "component" tag
 //
-fn build_hashes(writer: &mut Writer<&mut Vec<u8>>, gem: &Gemspec) -> Result<()> {
+fn build_hashes<W: Write>(writer: &mut Writer<W>, gem: &Gemspec) -> Result<()> {
     for hash in &gem.hashes {
         writer
             .create_element("hash")
-            .with_attribute(("alg", "SHA-256"))
-            .write_text_content(BytesText::new(&hash.content))?;
+            .with_attribute(("alg", hash.alg.as_str()))
+            .write_text_content(text_content(&hash.content))?;
     }
 
     Ok(())
@@ -119,43 +380,117 @@ fn build_hashes(writer: &mut Writer<&mut Vec<u8>>, gem: &Gemspec) -> Result<()>
 //
 // Builds xml repersentatiom of "licenses" child tag for "component" tag
 //
-fn build_licanses(writer: &mut Writer<&mut Vec<u8>>, gem: &Gemspec) -> Result<()> {
-    writer
-        .create_element("license")
-        .write_inner_content(|writer| {
-            for license_type in &gem.licenses {
-                match license_type {
-                    License::KnownLicense(license) => {
+fn build_licanses<W: Write>(writer: &mut Writer<W>, gem: &Gemspec) -> Result<()> {
+    for license_type in &gem.licenses {
+        match license_type {
+            License::KnownLicense(license) => {
+                writer
+                    .create_element("license")
+                    .write_inner_content(|writer| {
                         writer
                             .create_element("id")
-                            .write_text_content(BytesText::new(&license.id))?;
-                    }
-                    License::UnknownLicense(license) => {
+                            .write_text_content(text_content(&license.id))?;
+
+                        Ok(())
+                    })?;
+            }
+            License::UnknownLicense(license) => {
+                writer
+                    .create_element("license")
+                    .write_inner_content(|writer| {
                         writer
                             .create_element("name")
-                            .write_text_content(BytesText::new(&license.name))?;
-                    }
-                }
+                            .write_text_content(text_content(&license.name))?;
+
+                        Ok(())
+                    })?;
             }
+            License::Expression(license) => {
+                writer
+                    .create_element("expression")
+                    .write_text_content(text_content(&license.expression))?;
+            }
+        }
+    }
 
-            Ok(())
-        })?;
+    Ok(())
+}
+
+//
+// Builds xml repersentatiom of "externalReferences" child tag for "component" tag
+//
+fn build_references<W: Write>(writer: &mut Writer<W>, gem: &Gemspec) -> Result<()> {
+    for reference in &gem.external_references {
+        writer
+            .create_element("reference")
+            .with_attribute(("type", reference.reference_type.as_str()))
+            .write_inner_content(|writer| {
+                writer
+                    .create_element("url")
+                    .write_text_content(text_content(&reference.url))?;
+
+                Ok(())
+            })?;
+    }
+
+    Ok(())
+}
+
+//
+// Builds xml repersentatiom of "property" tags for "properties" tag of component or metadata
+//
+fn build_properties<W: Write>(writer: &mut Writer<W>, properties: &[Property]) -> Result<()> {
+    for property in properties {
+        writer
+            .create_element("property")
+            .with_attribute(("name", property.name.as_str()))
+            .write_text_content(text_content(&property.value))?;
+    }
 
     Ok(())
 }
 
+//
+// Escapes text content of xml element. quick-xml escapes markup characters,
+// but control characters are forbidden in XML 1.0 even in escaped form,
+// so they are dropped from the content
+//
+fn text_content(content: &str) -> BytesText<'static> {
+    let valid_content: String = content.chars().filter(|ch| is_xml_char(*ch)).collect();
+
+    BytesText::from_escaped(escape(valid_content).into_owned())
+}
+
+//
+// Checks that character allowed by XML 1.0 specification
+//
+fn is_xml_char(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{9}'
+            | '\u{A}'
+            | '\u{D}'
+            | '\u{20}'..='\u{D7FF}'
+            | '\u{E000}'..='\u{FFFD}'
+            | '\u{10000}'..='\u{10FFFF}'
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::gem::licenses::{KnownLicense, License, UnknownLicense};
-    use crate::gem::HashSpec;
+    use crate::bom_se::ComponentType;
+    use crate::gem::licenses::{
+        get_licenses, KnownLicense, License, LicenseExpression, UnknownLicense,
+    };
+    use crate::gem::{ExternalReference, HashSpec, Property, Scope};
 
     #[test]
     fn test_when_no_components() {
         let gems: Vec<Gemspec> = Vec::new();
         let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
 
-        let xml = build_xml(&gems, &serial).unwrap();
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
         let expected = r#"<?xml version="1.0" encoding="utf-8"?>
 <bom xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" serialNumber="urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42" version="1" xmlns="http://cyclonedx.org/schema/bom/1.5">
   <components>
@@ -171,19 +506,21 @@ mod tests {
             name: String::from("activemodel"),
             version: String::from("7.0.8.4"),
             purl: String::from("pkg:gem/activemodel@7.0.8.4"),
+            bom_ref: String::from("pkg:gem/activemodel@7.0.8.4"),
             author: String::from("David Heinemeier Hansson"),
             licenses: vec![License::KnownLicense(KnownLicense::new(String::from("MIT")))],
             description: String::from("A toolkit for building modeling frameworks like Active Record. Rich support for attributes, callbacks, validations, serialization, internationalization, and testing."),
             hashes: vec!(HashSpec{
                 alg: String::from("SHA-256"),
                 content: String::from("ef4e092d8644121b3e756e831bed6a16878317d02b9611bec8efcfdaee6525d6")
-            })
-
+            }),
+            ..Default::default()
         };
         let second_gem = Gemspec {
             name: String::from("brakeman"),
             version: String::from("6.0.1"),
             purl: String::from("pkg:gem/brakeman@6.0.1"),
+            bom_ref: String::from("pkg:gem/brakeman@6.0.1"),
             author: String::from("Justin Collins"),
             licenses: vec![License::UnknownLicense(UnknownLicense::new(String::from(
                 "Brakeman Public Use License",
@@ -195,16 +532,18 @@ mod tests {
                     "39641c63bc247bbdf993a349de90a13e146c464c872191f2adc12555bde591be",
                 ),
             }],
+            ..Default::default()
         };
 
         let gems = vec![first_gem, second_gem];
         let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
 
-        let xml = build_xml(&gems, &serial).unwrap();
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
         let expected = r#"<?xml version="1.0" encoding="utf-8"?>
 <bom xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" serialNumber="urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42" version="1" xmlns="http://cyclonedx.org/schema/bom/1.5">
   <components>
-    <component type="library">
+    <component type="library" bom-ref="pkg:gem/activemodel@7.0.8.4">
+      <author>David Heinemeier Hansson</author>
       <name>activemodel</name>
       <version>7.0.8.4</version>
       <description>A toolkit for building modeling frameworks like Active Record. Rich support for attributes, callbacks, validations, serialization, internationalization, and testing.</description>
@@ -218,7 +557,8 @@ mod tests {
       </licenses>
       <purl>pkg:gem/activemodel@7.0.8.4</purl>
     </component>
-    <component type="library">
+    <component type="library" bom-ref="pkg:gem/brakeman@6.0.1">
+      <author>Justin Collins</author>
       <name>brakeman</name>
       <version>6.0.1</version>
       <description>Security vulnerability scanner for Ruby on Rails.</description>
@@ -233,8 +573,521 @@ mod tests {
       <purl>pkg:gem/brakeman@6.0.1</purl>
     </component>
   </components>
+  <dependencies>
+    <dependency ref="pkg:gem/activemodel@7.0.8.4"/>
+    <dependency ref="pkg:gem/brakeman@6.0.1"/>
+  </dependencies>
 </bom>"#;
 
         assert_eq!(xml, expected);
     }
+
+    //
+    // Parses xml and returns text content of all "description" tags
+    //
+    fn read_descriptions(xml: &str) -> Vec<String> {
+        use quick_xml::reader::Reader;
+
+        let mut reader = Reader::from_str(xml);
+        let mut in_description = false;
+        let mut descriptions = Vec::new();
+
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(tag) if tag.name().as_ref() == b"description" => in_description = true,
+                Event::End(tag) if tag.name().as_ref() == b"description" => in_description = false,
+                Event::Text(text) if in_description => {
+                    descriptions.push(text.unescape().unwrap().into_owned())
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        descriptions
+    }
+
+    fn build_gem(description: &str) -> Gemspec {
+        Gemspec {
+            name: String::from("escaped"),
+            version: String::from("1.0.0"),
+            purl: String::from("pkg:gem/escaped@1.0.0"),
+            description: String::from(description),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_description_with_markup_characters() {
+        let gems = vec![build_gem("A & B <tag>")];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert!(xml.contains("<description>A &amp; B &lt;tag&gt;</description>"));
+        assert_eq!(read_descriptions(&xml), vec!["A & B <tag>"]);
+    }
+
+    #[test]
+    fn test_description_round_trip() {
+        let descriptions = [
+            "Quotes \"double\" and 'single'",
+            "Already escaped &amp; entity",
+            "CDATA end ]]> inside",
+            "Nokogiri (鋸) makes it easy — ñ",
+        ];
+        let gems: Vec<Gemspec> = descriptions.iter().map(|d| build_gem(d)).collect();
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert_eq!(read_descriptions(&xml), descriptions);
+    }
+
+    #[test]
+    fn test_description_with_control_characters() {
+        let gems = vec![build_gem("Bell\u{7} and\u{0} null\tstay tab")];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert_eq!(read_descriptions(&xml), vec!["Bell and null\tstay tab"]);
+    }
+
+    #[test]
+    fn test_when_multiple_hashes() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            hashes: vec![
+                HashSpec::with_alg("SHA-256", String::from("66e736acc1d1ba5ca1b598fc8b6024715aee02025467bfa87682dbeb823ddc17")),
+                HashSpec::with_alg("SHA-512", String::from("1334f84a0a056646f698ca231171f3ff86324d63d684d8a0c13a6fe9dc61faaa0e8ef8acadd8c322567e8f8d2812711b2646fd27da33887be3d04f82a83a9cde")),
+            ],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <hashes>
+        <hash alg="SHA-256">66e736acc1d1ba5ca1b598fc8b6024715aee02025467bfa87682dbeb823ddc17</hash>
+        <hash alg="SHA-512">1334f84a0a056646f698ca231171f3ff86324d63d684d8a0c13a6fe9dc61faaa0e8ef8acadd8c322567e8f8d2812711b2646fd27da33887be3d04f82a83a9cde</hash>
+      </hashes>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_properties() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            properties: vec![Property::new("cdx:rubygems:stale", String::from("true"))],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <purl>pkg:gem/rails@7.1.1</purl>
+      <properties>
+        <property name="cdx:rubygems:stale">true</property>
+      </properties>
+    </component>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_hash_alg_attribute_follows_hash_spec() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            hashes: vec![HashSpec {
+                alg: String::from("SHA-512"),
+                content: String::from("1334f84a0a056646f698ca231171f3ff86324d63d684d8a0c13a6fe9dc61faaa0e8ef8acadd8c322567e8f8d2812711b2646fd27da33887be3d04f82a83a9cde"),
+            }],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert!(xml.contains(r#"<hash alg="SHA-512">1334f84a0a"#));
+        assert!(!xml.contains("SHA-256"));
+    }
+
+    #[test]
+    fn test_when_component_has_external_references() {
+        let gems = vec![Gemspec {
+            name: String::from("nokogiri"),
+            version: String::from("1.16.5"),
+            purl: String::from("pkg:gem/nokogiri@1.16.5?platform=x86_64-linux"),
+            external_references: vec![ExternalReference::new(
+                "distribution",
+                String::from("https://rubygems.org/gems/nokogiri-1.16.5-x86_64-linux.gem"),
+            )],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <purl>pkg:gem/nokogiri@1.16.5?platform=x86_64-linux</purl>
+      <externalReferences>
+        <reference type="distribution">
+          <url>https://rubygems.org/gems/nokogiri-1.16.5-x86_64-linux.gem</url>
+        </reference>
+      </externalReferences>
+    </component>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_license_expression() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            licenses: vec![License::Expression(LicenseExpression::new(String::from(
+                "MIT OR Apache-2.0",
+            )))],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <licenses>
+        <expression>MIT OR Apache-2.0</expression>
+      </licenses>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_write_to_file_same_as_string() {
+        let gems = vec![Gemspec {
+            name: String::from("base64"),
+            version: String::from("0.3.0"),
+            purl: String::from("pkg:gem/base64@0.3.0"),
+            licenses: vec![License::KnownLicense(KnownLicense::new(String::from(
+                "Ruby",
+            )))],
+            ..Default::default()
+        }];
+        let info = BomInfo::new("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+        let file_name = std::env::temp_dir().join("cyclonedx-rs-gem-xml-write.xml");
+
+        let file = std::fs::File::create(&file_name).unwrap();
+        write(&gems, &info, std::io::BufWriter::new(file)).unwrap();
+        let written = std::fs::read(&file_name).unwrap();
+        std::fs::remove_file(&file_name).unwrap();
+
+        assert_eq!(written, build_xml(&gems, &info).unwrap().into_bytes());
+    }
+
+    #[test]
+    fn test_when_multiple_licenses() {
+        let gems = vec![Gemspec {
+            name: String::from("base64"),
+            version: String::from("0.3.0"),
+            purl: String::from("pkg:gem/base64@0.3.0"),
+            licenses: vec![
+                License::KnownLicense(KnownLicense::new(String::from("Ruby"))),
+                License::UnknownLicense(UnknownLicense::new(String::from("Nonstandard"))),
+            ],
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <licenses>
+        <license>
+          <id>Ruby</id>
+        </license>
+        <license>
+          <name>Nonstandard</name>
+        </license>
+      </licenses>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_license_expression_with_other_licenses() {
+        let gems = vec![Gemspec {
+            name: String::from("base64"),
+            version: String::from("0.3.0"),
+            purl: String::from("pkg:gem/base64@0.3.0"),
+            licenses: get_licenses(Some(vec![
+                String::from("Ruby"),
+                String::from("MIT OR Apache-2.0"),
+            ])),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <licenses>
+        <expression>Ruby AND (MIT OR Apache-2.0)</expression>
+      </licenses>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_scope() {
+        let gems = vec![Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            description: String::from("Rails"),
+            scope: Some(Scope::Required),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <description>Rails</description>
+      <scope>required</scope>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_type() {
+        let gems = vec![Gemspec {
+            component_type: ComponentType::Framework,
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            bom_ref: String::from("pkg:gem/rails@7.1.1"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert!(xml.contains(r#"<component type="framework" bom-ref="pkg:gem/rails@7.1.1">"#));
+    }
+
+    #[test]
+    fn test_when_components_have_dependencies() {
+        let gems = vec![
+            Gemspec {
+                name: String::from("actioncable"),
+                version: String::from("7.0.8.4"),
+                purl: String::from("pkg:gem/actioncable@7.0.8.4"),
+                bom_ref: String::from("pkg:gem/actioncable@7.0.8.4"),
+                dependencies: vec![String::from("actionpack"), String::from("nio4r")],
+                ..Default::default()
+            },
+            Gemspec {
+                name: String::from("actionpack"),
+                version: String::from("7.0.8.4"),
+                purl: String::from("pkg:gem/actionpack@7.0.8.4"),
+                bom_ref: String::from("pkg:gem/actionpack@7.0.8.4"),
+                ..Default::default()
+            },
+        ];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"  <dependencies>
+    <dependency ref="pkg:gem/actioncable@7.0.8.4">
+      <dependency ref="pkg:gem/actionpack@7.0.8.4"/>
+    </dependency>
+    <dependency ref="pkg:gem/actionpack@7.0.8.4"/>
+  </dependencies>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_bom_has_annotations() {
+        let gems: Vec<Gemspec> = Vec::new();
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+        let annotations = crate::bom_se::build_annotations(
+            &[String::from("Release ticket REL-1234")],
+            &serial,
+            "2024-05-13T15:24:55Z",
+        );
+
+        let xml = build_xml(
+            &gems,
+            &BomInfo {
+                annotations,
+                ..BomInfo::new(&serial)
+            },
+        )
+        .unwrap();
+        let expected = format!(
+            r#"  <annotations>
+    <annotation>
+      <subjects>
+        <subject ref="urn:cdx:b83ca3d9-6b17-4566-bd50-201af63d9c42/1"/>
+      </subjects>
+      <annotator>
+        <component type="application">
+          <name>cyclonedx-rs-gem</name>
+          <version>{}</version>
+        </component>
+      </annotator>
+      <timestamp>2024-05-13T15:24:55Z</timestamp>
+      <text>Release ticket REL-1234</text>
+    </annotation>
+  </annotations>"#,
+            env!("CARGO_PKG_VERSION")
+        );
+
+        assert!(xml.contains(&expected));
+    }
+
+    #[test]
+    fn test_when_component_has_no_licenses() {
+        let gems = vec![Gemspec {
+            name: String::from("rake"),
+            version: String::from("13.0.6"),
+            purl: String::from("pkg:gem/rake@13.0.6"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert!(!xml.contains("license"));
+    }
+
+    #[test]
+    fn test_when_component_has_group() {
+        let gems = vec![Gemspec {
+            group: Some(String::from("gems.mycompany.com")),
+            name: String::from("private_gem"),
+            version: String::from("1.0.0"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"    <component type="library">
+      <group>gems.mycompany.com</group>
+      <name>private_gem</name>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_author_and_group() {
+        let gems = vec![Gemspec {
+            author: String::from("Mike Dalessio, Aaron Patterson"),
+            group: Some(String::from("rubygems.org")),
+            name: String::from("nokogiri"),
+            version: String::from("1.16.5"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"    <component type="library">
+      <author>Mike Dalessio, Aaron Patterson</author>
+      <group>rubygems.org</group>
+      <name>nokogiri</name>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_copyright() {
+        let gems = vec![Gemspec {
+            name: String::from("rake"),
+            version: String::from("13.0.6"),
+            purl: String::from("pkg:gem/rake@13.0.6"),
+            copyright: Some(String::from("Copyright (c) Jim Weirich")),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <copyright>Copyright (c) Jim Weirich</copyright>
+      <purl>pkg:gem/rake@13.0.6</purl>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_no_hashes() {
+        let gems = vec![Gemspec {
+            name: String::from("annotate"),
+            version: String::from("3.2.0"),
+            purl: String::from("pkg:gem/annotate@3.2.0"),
+            ..Default::default()
+        }];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&gems, &BomInfo::new(&serial)).unwrap();
+
+        assert!(!xml.contains("hashes"));
+    }
+
+    #[test]
+    fn test_when_bom_has_metadata() {
+        let gems: Vec<Gemspec> = Vec::new();
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+        let metadata = crate::bom_se::build_metadata(&crate::bundler::Environment {
+            ruby_version: Some(String::from("ruby 3.3.0p0")),
+            bundler_version: Some(String::from("2.5.9")),
+        });
+
+        let xml = build_xml(
+            &gems,
+            &BomInfo {
+                metadata,
+                ..BomInfo::new(&serial)
+            },
+        )
+        .unwrap();
+        let expected = r#"  <metadata>
+    <tools>
+      <components>
+        <component type="application">
+          <name>bundler</name>
+          <version>2.5.9</version>
+          <purl>pkg:gem/bundler@2.5.9</purl>
+        </component>
+      </components>
+    </tools>
+    <properties>
+      <property name="cdx:bundler:ruby:version">ruby 3.3.0p0</property>
+    </properties>
+  </metadata>
+  <components>"#;
+
+        assert!(xml.contains(expected));
+    }
+
+    #[test]
+    fn test_when_component_has_occurrences() {
+        let mut gem = Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            ..Default::default()
+        };
+        gem.add_occurrence("services/billing/Gemfile.lock");
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let xml = build_xml(&vec![gem], &BomInfo::new(&serial)).unwrap();
+        let expected = r#"      <purl>pkg:gem/rails@7.1.1</purl>
+      <evidence>
+        <occurrences>
+          <occurrence>
+            <location>services/billing/Gemfile.lock</location>
+          </occurrence>
+        </occurrences>
+      </evidence>
+    </component>"#;
+
+        assert!(xml.contains(expected));
+    }
 }
@@ -0,0 +1,127 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use super::json::Bom;
+use super::BomInfo;
+use crate::gem::Gemspec;
+
+///
+/// Writes gems collection as yaml to output. Bom has the same structure
+/// as json one
+///
+pub(super) fn write<W: Write>(gems: &Vec<Gemspec>, info: &BomInfo, mut output: W) -> Result<()> {
+    serde_yaml::to_writer(&mut output, &Bom::new(gems, info))?;
+
+    Ok(output.flush()?)
+}
+
+//
+// Builds bom.yaml file content as string. A separate function need for
+// testing
+//
+#[cfg(test)]
+fn build_yaml(gems: &Vec<Gemspec>, info: &BomInfo) -> Result<String> {
+    Ok(serde_yaml::to_string(&Bom::new(gems, info))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gem::licenses::{KnownLicense, License, UnknownLicense};
+    use crate::gem::HashSpec;
+
+    #[test]
+    fn test_when_no_components() {
+        let gems: Vec<Gemspec> = Vec::new();
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let yaml = build_yaml(&gems, &BomInfo::new(&serial));
+        let expected = r#"bomFormat: CycloneDX
+specVersion: '1.5'
+serialNumber: urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42
+version: 1
+components: []
+"#;
+        assert_eq!(yaml.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_when_some_components() {
+        let first_gem = Gemspec {
+            name: String::from("activemodel"),
+            version: String::from("7.0.8.4"),
+            purl: String::from("pkg:gem/activemodel@7.0.8.4"),
+            bom_ref: String::from("pkg:gem/activemodel@7.0.8.4"),
+            author: String::from("David Heinemeier Hansson"),
+            licenses: vec![License::KnownLicense(KnownLicense::new(String::from("MIT")))],
+            description: String::from("A toolkit for building modeling frameworks like Active Record. Rich support for attributes, callbacks, validations, serialization, internationalization, and testing."),
+            hashes: vec!(HashSpec{
+                alg: String::from("SHA-256"),
+                content: String::from("ef4e092d8644121b3e756e831bed6a16878317d02b9611bec8efcfdaee6525d6")
+            }),
+            ..Default::default()
+        };
+        let second_gem = Gemspec {
+            name: String::from("brakeman"),
+            version: String::from("6.0.1"),
+            purl: String::from("pkg:gem/brakeman@6.0.1"),
+            bom_ref: String::from("pkg:gem/brakeman@6.0.1"),
+            author: String::from("Justin Collins"),
+            licenses: vec![License::UnknownLicense(UnknownLicense::new(String::from(
+                "Brakeman Public Use License",
+            )))],
+            description: String::from("Security vulnerability scanner for Ruby on Rails."),
+            hashes: vec![HashSpec {
+                alg: String::from("SHA-256"),
+                content: String::from(
+                    "39641c63bc247bbdf993a349de90a13e146c464c872191f2adc12555bde591be",
+                ),
+            }],
+            ..Default::default()
+        };
+
+        let gems = vec![first_gem, second_gem];
+        let serial = String::from("urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42");
+
+        let yaml = build_yaml(&gems, &BomInfo::new(&serial));
+        let expected = r#"bomFormat: CycloneDX
+specVersion: '1.5'
+serialNumber: urn:uuid:b83ca3d9-6b17-4566-bd50-201af63d9c42
+version: 1
+components:
+- type: library
+  bom-ref: pkg:gem/activemodel@7.0.8.4
+  name: activemodel
+  version: 7.0.8.4
+  purl: pkg:gem/activemodel@7.0.8.4
+  licenses:
+  - license:
+      id: MIT
+      properties: []
+  author: David Heinemeier Hansson
+  description: A toolkit for building modeling frameworks like Active Record. Rich support for attributes, callbacks, validations, serialization, internationalization, and testing.
+  hashes:
+  - alg: SHA-256
+    content: ef4e092d8644121b3e756e831bed6a16878317d02b9611bec8efcfdaee6525d6
+- type: library
+  bom-ref: pkg:gem/brakeman@6.0.1
+  name: brakeman
+  version: 6.0.1
+  purl: pkg:gem/brakeman@6.0.1
+  licenses:
+  - license:
+      name: Brakeman Public Use License
+      properties: []
+  author: Justin Collins
+  description: Security vulnerability scanner for Ruby on Rails.
+  hashes:
+  - alg: SHA-256
+    content: 39641c63bc247bbdf993a349de90a13e146c464c872191f2adc12555bde591be
+dependencies:
+- ref: pkg:gem/activemodel@7.0.8.4
+- ref: pkg:gem/brakeman@6.0.1
+"#;
+        assert_eq!(yaml.unwrap(), expected);
+    }
+}
@@ -1,63 +1,228 @@
+use std::collections::HashSet;
+
+use clap::ValueEnum;
 use regex::Regex;
+use url::Url;
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
 
 #[derive(Debug)]
 pub(crate) struct Source {
     name: String,
-    version: String,
+    // gems without version in Gemfile.lock are resolved to latest one
+    version: Option<String>,
     platform: Option<String>,
+    remote: Option<String>,
+    dependencies: Vec<String>,
+    // commit of git repository for gems from GIT sections
+    revision: Option<String>,
 }
 
 pub(crate) struct Gemfile {
     pub(crate) gems: Vec<Source>,
+    // gems from GIT sections, they are not published to gems registry
+    pub(crate) git_gems: Vec<Source>,
+    // names of gems from DEPENDENCIES section, i.e. declared in Gemfile
+    pub(crate) direct: Vec<String>,
+    pub(crate) environment: Environment,
+}
+
+///
+/// Ruby and bundler versions from RUBY VERSION and BUNDLED WITH sections
+///
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Environment {
+    // e.g. `ruby 3.3.0p0`
+    pub(crate) ruby_version: Option<String>,
+    pub(crate) bundler_version: Option<String>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub(crate) enum DedupeKey {
+    // same name, version and platform, i.e. same purl
+    Purl,
+
+    // same name and version, platform specific builds are merged
+    NameVersion,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug, Default)]
+pub(crate) enum FetchOrder {
+    // as gems are listed in Gemfile.lock
+    #[default]
+    Lockfile,
+
+    // sorted by gem name
+    Alphabetical,
+
+    // gems from DEPENDENCIES section first, then transitive ones
+    DirectFirst,
 }
 
 ///
 /// Main method, parses Gemfile.lock content and returns vector,
 /// which contains names and versions all installed gems from gems
-/// repository (rubygems.org)
+/// repository (rubygems.org). Every gem is linked with remote of its
+/// GEM section, `remote:` line may be placed before or after `specs:`.
+/// Gems from GIT sections are collected separately with their revision
 ///
-pub(crate) fn parse_gemfile(gemfile_content: String, verbose: bool) -> Gemfile {
+pub(crate) fn parse_gemfile(gemfile_content: String) -> Gemfile {
     let mut gems: Vec<Source> = Vec::new();
+    let mut git_gems: Vec<Source> = Vec::new();
+    let mut section_remotes: Vec<String> = Vec::new();
+    let mut section_gems: Vec<Source> = Vec::new();
+    let mut direct: Vec<String> = Vec::new();
+    let mut environment = Environment::default();
+    let mut section_revision: Option<String> = None;
     let mut gems_section = false;
+    let mut git_section = false;
+    let mut dependencies_section = false;
+    let mut ruby_section = false;
+    let mut bundler_section = false;
 
     let gem_section_regexp = Regex::new(r"^GEM$").unwrap();
-    let other_section_regexp = Regex::new(r"^[A-Z]+$").unwrap();
-    let spec_regexp = Regex::new(r"^\s{4}(\S+?)\s+?\((\S+?)\)$").unwrap();
+    let git_section_regexp = Regex::new(r"^GIT$").unwrap();
+    let dependencies_section_regexp = Regex::new(r"^DEPENDENCIES$").unwrap();
+    let ruby_section_regexp = Regex::new(r"^RUBY VERSION$").unwrap();
+    let bundler_section_regexp = Regex::new(r"^BUNDLED WITH$").unwrap();
+    let other_section_regexp = Regex::new(r"^[A-Z]+( [A-Z]+)*$").unwrap();
+    let remote_regexp = Regex::new(r"^\s{2}remote:\s+(\S+)$").unwrap();
+    let revision_regexp = Regex::new(r"^\s{2}revision:\s+(\S+)$").unwrap();
+    let spec_regexp = Regex::new(r"^\s{4}(\S+?)(?:\s+?\((\S+?)\))?$").unwrap();
+    let dependency_regexp = Regex::new(r"^\s{6}(\S+)").unwrap();
+    let direct_regexp = Regex::new(r"^\s{2}([^\s!]+)").unwrap();
+    let environment_regexp = Regex::new(r"^\s+(\S.*?)\s*$").unwrap();
 
-    let lines = gemfile_content.lines();
+    // lines() also strips `\r` of Windows line endings
+    let lines = gemfile_content
+        .strip_prefix(BYTE_ORDER_MARK)
+        .unwrap_or(&gemfile_content)
+        .lines();
 
     for line in lines {
         if other_section_regexp.is_match(line) {
-            if gem_section_regexp.is_match(line) {
-                gems_section = true;
-            } else if gems_section {
-                break;
+            let target = if git_section {
+                &mut git_gems
+            } else {
+                &mut gems
+            };
+            flush_section(target, &mut section_remotes, &mut section_gems);
+            gems_section = gem_section_regexp.is_match(line);
+            git_section = git_section_regexp.is_match(line);
+            dependencies_section = dependencies_section_regexp.is_match(line);
+            ruby_section = ruby_section_regexp.is_match(line);
+            bundler_section = bundler_section_regexp.is_match(line);
+            section_revision = None;
+
+            continue;
+        }
+
+        if ruby_section || bundler_section {
+            if let Some(captures) = environment_regexp.captures(line) {
+                let value = Some(String::from(captures.get(1).unwrap().as_str()));
+                if ruby_section {
+                    environment.ruby_version = value;
+                } else {
+                    environment.bundler_version = value;
+                }
             }
 
             continue;
         }
 
-        if gems_section && spec_regexp.is_match(line) {
-            let captures = spec_regexp.captures(line).unwrap();
-            let version_info = parse_gem_version(captures.get(2).unwrap().as_str());
+        if dependencies_section {
+            if let Some(captures) = direct_regexp.captures(line) {
+                direct.push(String::from(captures.get(1).unwrap().as_str()));
+            }
 
-            gems.push(Source {
+            continue;
+        }
+
+        if !gems_section && !git_section {
+            continue;
+        }
+
+        if let Some(captures) = remote_regexp.captures(line) {
+            section_remotes.push(String::from(captures.get(1).unwrap().as_str()));
+        } else if let Some(captures) = revision_regexp.captures(line) {
+            section_revision = Some(String::from(captures.get(1).unwrap().as_str()));
+        } else if let Some(captures) = spec_regexp.captures(line) {
+            let (version, platform) = match captures.get(2) {
+                Some(version) => {
+                    let (version, platform) = parse_gem_version(version.as_str());
+                    (Some(version), platform)
+                }
+                None => (None, None),
+            };
+
+            section_gems.push(Source {
                 name: String::from(captures.get(1).unwrap().as_str()),
-                version: version_info.0,
-                platform: version_info.1,
+                version,
+                platform,
+                remote: None,
+                dependencies: Vec::new(),
+                revision: section_revision.clone(),
             });
+        } else if let Some(captures) = dependency_regexp.captures(line) {
+            if let Some(gem) = section_gems.last_mut() {
+                gem.dependencies
+                    .push(String::from(captures.get(1).unwrap().as_str()));
+            }
         }
     }
 
-    let result = Gemfile { gems };
+    let target = if git_section {
+        &mut git_gems
+    } else {
+        &mut gems
+    };
+    flush_section(target, &mut section_remotes, &mut section_gems);
 
-    if verbose {
-        result.show_info();
-    }
+    let result = Gemfile {
+        gems,
+        git_gems,
+        direct,
+        environment,
+    };
+
+    result.show_info();
 
     result
 }
 
+//
+// Links gems of finished GEM section with the section remote and moves
+// them to result list. If section contains several remotes, the first
+// one is used. Repeated specs, left by bad merges, are kept once
+//
+fn flush_section(
+    gems: &mut Vec<Source>,
+    remotes: &mut Vec<String>,
+    section_gems: &mut Vec<Source>,
+) {
+    let remote = remotes.first().cloned();
+    let mut seen = HashSet::new();
+
+    section_gems.retain(|gem| {
+        let is_new = seen.insert((gem.name.clone(), gem.version.clone(), gem.platform.clone()));
+        if !is_new {
+            log::warn!(
+                "Gem {} {} is listed several times in Gemfile.lock, duplicates are skipped",
+                gem.name,
+                gem.version.as_deref().unwrap_or_default()
+            );
+        }
+
+        is_new
+    });
+
+    gems.extend(section_gems.drain(..).map(|gem| Source {
+        remote: remote.clone(),
+        ..gem
+    }));
+    remotes.clear();
+}
+
 //
 // Try to find platform specific version of gem
 //
@@ -69,10 +234,121 @@ fn parse_gem_version(version_str: &str) -> (String, Option<String>) {
 }
 
 impl Gemfile {
+    ///
+    /// Returns gems, which GEM or GIT section remote host is in the denied
+    /// list, in `name version (remote)` form
+    ///
+    pub(crate) fn find_denied_sources(&self, denied_hosts: &[String]) -> Vec<String> {
+        self.gems
+            .iter()
+            .chain(&self.git_gems)
+            .filter(|gem| gem.is_from_hosts(denied_hosts))
+            .map(|gem| {
+                format!(
+                    "{} {} ({})",
+                    gem.name,
+                    gem.version.as_deref().unwrap_or_default(),
+                    gem.get_remote().unwrap_or_default()
+                )
+            })
+            .collect()
+    }
+
+    ///
+    /// Removes repeated gems, keeping the first one. Gems are compared by
+    /// given key, so platform variants are merged only with `NameVersion`
+    ///
+    pub(crate) fn dedupe(&mut self, key: DedupeKey) {
+        let mut seen = HashSet::new();
+
+        self.gems.retain(|gem| {
+            let (name, version, platform) = gem.get_source();
+            let platform = match key {
+                DedupeKey::Purl => platform,
+                DedupeKey::NameVersion => None,
+            };
+
+            seen.insert((
+                name.to_string(),
+                version.map(String::from),
+                platform.map(String::from),
+            ))
+        });
+    }
+
+    ///
+    /// Orders gems for fetching, requests are sent in the resulting order
+    ///
+    pub(crate) fn order_gems(&mut self, order: FetchOrder) {
+        match order {
+            FetchOrder::Lockfile => {}
+            FetchOrder::Alphabetical => self.gems.sort_by(|a, b| a.name.cmp(&b.name)),
+            FetchOrder::DirectFirst => {
+                let direct = &self.direct;
+                self.gems.sort_by_key(|gem| !direct.contains(&gem.name));
+            }
+        }
+    }
+
+    ///
+    /// Keeps only gems, which names match given pattern
+    ///
+    pub(crate) fn filter_names(&mut self, pattern: &Regex) {
+        self.gems.retain(|gem| pattern.is_match(&gem.name));
+        self.git_gems.retain(|gem| pattern.is_match(&gem.name));
+    }
+
+    ///
+    /// Keeps only given direct gems and gems they depend on, directly
+    /// or transitively
+    ///
+    pub(crate) fn retain_dependencies_of(&mut self, names: &[String]) {
+        let mut reachable: HashSet<String> = HashSet::new();
+        let mut queue: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        while let Some(name) = queue.pop() {
+            if !reachable.insert(String::from(name)) {
+                continue;
+            }
+            for gem in self
+                .gems
+                .iter()
+                .chain(&self.git_gems)
+                .filter(|gem| gem.name == name)
+            {
+                queue.extend(gem.dependencies.iter().map(String::as_str));
+            }
+        }
+
+        self.gems.retain(|gem| reachable.contains(&gem.name));
+        self.git_gems.retain(|gem| reachable.contains(&gem.name));
+        self.direct.retain(|name| reachable.contains(name));
+    }
+
     fn show_info(&self) {
         let length = self.gems.len();
 
-        println!("\nGemfile.lock file total contains {} gems\n", length);
+        log::debug!(
+            target: "gemfile_parsed",
+            "\nGemfile.lock file total contains {} gems\n",
+            length
+        );
+
+        let mut remotes: Vec<&str> = self.gems.iter().filter_map(Source::get_remote).collect();
+        remotes.sort_unstable();
+        remotes.dedup();
+
+        if remotes.len() > 1 {
+            for remote in remotes {
+                let count = self
+                    .gems
+                    .iter()
+                    .filter(|gem| gem.get_remote() == Some(remote))
+                    .count();
+
+                log::debug!(target: "gemfile_remote", "{} gems from {}", count, remote);
+            }
+        }
     }
 }
 
@@ -80,21 +356,119 @@ impl Source {
     //
     // Returns Gemfile.lock item by tuple contains (name, version, platform)
     //
-    pub(crate) fn get_source(&self) -> (&str, &str, Option<&str>) {
-        match &self.platform {
-            Some(platform) => (&self.name, &self.version, Some(platform)),
-            None => (&self.name, &self.version, None),
+    pub(crate) fn get_source(&self) -> (&str, Option<&str>, Option<&str>) {
+        (
+            &self.name,
+            self.version.as_deref(),
+            self.platform.as_deref(),
+        )
+    }
+
+    ///
+    /// Checks if gem version is pinned in Gemfile.lock
+    ///
+    pub(crate) fn has_version(&self) -> bool {
+        self.version.is_some()
+    }
+
+    //
+    // Checks if gem remote host is one of the given hosts
+    //
+    fn is_from_hosts(&self, hosts: &[String]) -> bool {
+        match self.get_remote_host() {
+            Some(remote_host) => hosts
+                .iter()
+                .any(|host| host.eq_ignore_ascii_case(&remote_host)),
+            None => false,
         }
     }
+
+    //
+    // Returns host of GEM or GIT section remote, e.g. `rubygems.org`. GIT
+    // remotes could be written in scp-like form `git@github.com:org/repo.git`
+    //
+    pub(crate) fn get_remote_host(&self) -> Option<String> {
+        let remote = self.get_remote()?;
+
+        match Url::parse(remote) {
+            Ok(url) => url.host_str().map(String::from),
+            Err(_) => remote
+                .split_once(':')
+                .map(|(user_host, _)| user_host.rsplit('@').next().unwrap_or(user_host))
+                .filter(|host| !host.is_empty())
+                .map(String::from),
+        }
+    }
+
+    //
+    // Returns git commit for gems from GIT sections
+    //
+    pub(crate) fn get_revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+
+    //
+    // Returns names of gems, which the gem depends on
+    //
+    pub(crate) fn get_dependencies(&self) -> &[String] {
+        &self.dependencies
+    }
+
+    //
+    // Returns remote of GEM section, which contains the gem
+    //
+    pub(crate) fn get_remote(&self) -> Option<&str> {
+        self.remote.as_deref()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const SOME_GEMS_FIXTURE: &str = r#"
+GIT
+  remote: https://localgit.org/ruby/github_civisanalytics_ruby_audit.git
+  revision: add080c46d56bf8b1b7a229e1207cfbd43f5a282
+  branch: main
+  specs:
+    ruby_audit (2.3.0)
+      bundler-audit (~> 0.9.0)
+
+GEM
+  remote: https://rubygems.org/
+  specs:
+    actioncable (7.0.8.4)
+      actionpack (= 7.0.8.4)
+      activesupport (= 7.0.8.4)
+      nio4r (~> 2.0)
+      websocket-driver (>= 0.6.1)
+    choice (0.2.0)
+    clavius (1.0.4)
+    coderay (1.1.3)
+    nokogiri (1.16.5-arm64-darwin)
+    opentelemetry-instrumentation-net_http (0.20.0)
+
+PLATFORMS
+  arm64-darwin-23
+  x86_64-darwin-23
+  x86_64-linux-musl
+
+DEPENDENCIES
+  active_model_otp (~> 2.3)
+  active_record_union (~> 1.3)
+  activeadmin (~> 3.2)
+  activeadmin_addons!
+
+RUBY VERSION
+   ruby 3.3.0p0
+
+BUNDLED WITH
+   2.5.9"#;
+
     #[test]
     fn test_empty_string() {
-        let result = parse_gemfile(String::from(""), false);
+        let result = parse_gemfile(String::from(""));
 
         assert_eq!(result.gems.len(), 0);
     }
@@ -146,7 +520,7 @@ RUBY VERSION
 BUNDLED WITH
    2.5.9"#;
 
-        let result = parse_gemfile(String::from(gemfile), false);
+        let result = parse_gemfile(String::from(gemfile));
 
         assert_eq!(result.gems.len(), 0);
     }
@@ -183,77 +557,470 @@ RUBY VERSION
 BUNDLED WITH
    2.5.9"#;
 
-        let result = parse_gemfile(String::from(gemfile), false);
+        let result = parse_gemfile(String::from(gemfile));
 
         assert_eq!(result.gems.len(), 0);
     }
 
     #[test]
-    fn test_when_contains_some_gems() {
+    fn test_direct_dependencies() {
+        let gemfile = r#"GEM
+  remote: https://rubygems.org/
+  specs:
+    rake (13.0.6)
+    sneakers (2.13.0.pre)
+
+DEPENDENCIES
+  rake (~> 13.0)
+  sneakers!
+
+BUNDLED WITH
+   2.5.3
+"#;
+
+        let result = parse_gemfile(String::from(gemfile));
+
+        assert_eq!(result.direct, vec!["rake", "sneakers"]);
+    }
+
+    #[test]
+    fn test_duplicated_spec() {
+        let gemfile = r#"GEM
+  remote: https://rubygems.org/
+  specs:
+    nokogiri (1.16.4-x86_64-linux)
+      racc (~> 1.4)
+    racc (1.7.3)
+    nokogiri (1.16.4-x86_64-linux)
+      racc (~> 1.4)
+    nokogiri (1.16.4-arm64-darwin)
+      racc (~> 1.4)
+"#;
+
+        let result = parse_gemfile(String::from(gemfile));
+
+        let sources: Vec<_> = result.gems.iter().map(Source::get_source).collect();
+        assert_eq!(
+            sources,
+            [
+                ("nokogiri", Some("1.16.4"), Some("x86_64-linux")),
+                ("racc", Some("1.7.3"), None),
+                ("nokogiri", Some("1.16.4"), Some("arm64-darwin")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_order_gems() {
+        let gemfile = r#"GEM
+  remote: https://rubygems.org/
+  specs:
+    sneakers (2.13.0.pre)
+      bunny (~> 2.19)
+    bunny (2.22.0)
+    rake (13.0.6)
+
+DEPENDENCIES
+  rake (~> 13.0)
+  sneakers!
+"#;
+        let names = |order| {
+            let mut result = parse_gemfile(String::from(gemfile));
+            result.order_gems(order);
+
+            result
+                .gems
+                .iter()
+                .map(|gem| gem.name.clone())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(names(FetchOrder::Lockfile), ["sneakers", "bunny", "rake"]);
+        assert_eq!(
+            names(FetchOrder::Alphabetical),
+            ["bunny", "rake", "sneakers"]
+        );
+        assert_eq!(
+            names(FetchOrder::DirectFirst),
+            ["sneakers", "rake", "bunny"]
+        );
+    }
+
+    #[test]
+    fn test_retain_dependencies_of() {
+        let gemfile = r#"GEM
+  remote: https://rubygems.org/
+  specs:
+    amq-protocol (2.3.2)
+    bunny (2.22.0)
+      amq-protocol (~> 2.3)
+    rake (13.0.6)
+    sneakers (2.13.0.pre)
+      bunny (~> 2.19)
+
+DEPENDENCIES
+  rake (~> 13.0)
+  sneakers!
+"#;
+        let mut result = parse_gemfile(String::from(gemfile));
+
+        result.retain_dependencies_of(&[String::from("sneakers")]);
+
+        let names: Vec<&str> = result.gems.iter().map(|gem| gem.name.as_str()).collect();
+        assert_eq!(names, ["amq-protocol", "bunny", "sneakers"]);
+        assert_eq!(result.direct, vec!["sneakers"]);
+    }
+
+    #[test]
+    fn test_versionless_gems() {
+        let gemfile = r#"GIT
+  remote: https://localgit.org/ruby/github_jondot_sneakers.git
+  revision: dbe56bc84fc8b50088c71a800e33b1320a33bb3e
+  specs:
+    sneakers (2.13.0.pre)
+      thor
+    thor
+
+GEM
+  remote: https://rubygems.org/
+  specs:
+    rake (13.0.6)
+    thor
+"#;
+
+        let result = parse_gemfile(String::from(gemfile));
+
+        assert_eq!(result.git_gems.len(), 2);
+        assert_eq!(
+            result.git_gems[0].get_dependencies(),
+            &[String::from("thor")]
+        );
+        assert_eq!(result.git_gems[1].get_source(), ("thor", None, None));
+        assert_eq!(result.gems.len(), 2);
+        assert!(result.gems[0].has_version());
+        assert_eq!(result.gems[1].get_source(), ("thor", None, None));
+        assert!(!result.gems[1].has_version());
+    }
+
+    #[test]
+    fn test_git_gems() {
         let gemfile = r#"
 GIT
-  remote: https://localgit.org/ruby/github_civisanalytics_ruby_audit.git
-  revision: add080c46d56bf8b1b7a229e1207cfbd43f5a282
-  branch: main
+  remote: https://localgit.org/ruby/github_ctran_annotate_models.git
+  revision: 984c33e567c17fe6101ba367c880c7494c614561
   specs:
-    ruby_audit (2.3.0)
-      bundler-audit (~> 0.9.0)
+    annotate (3.2.0)
+      activerecord (>= 3.2, < 8.0)
+      rake (>= 10.4, < 14.0)
 
 GEM
   remote: https://rubygems.org/
   specs:
-    actioncable (7.0.8.4)
-      actionpack (= 7.0.8.4)
-      activesupport (= 7.0.8.4)
-      nio4r (~> 2.0)
-      websocket-driver (>= 0.6.1)
-    choice (0.2.0)
-    clavius (1.0.4)
-    coderay (1.1.3)
-    nokogiri (1.16.5-arm64-darwin)
-    opentelemetry-instrumentation-net_http (0.20.0)
+    rake (13.0.6)
 
 PLATFORMS
-  arm64-darwin-23
-  x86_64-darwin-23
-  x86_64-linux-musl
-
-DEPENDENCIES
-  active_model_otp (~> 2.3)
-  active_record_union (~> 1.3)
-  activeadmin (~> 3.2)
-  activeadmin_addons!
+  x86_64-linux
+"#;
 
-RUBY VERSION
-   ruby 3.3.0p0
+        let result = parse_gemfile(String::from(gemfile));
 
-BUNDLED WITH
-   2.5.9"#;
+        assert_eq!(result.gems.len(), 1);
+        assert_eq!(result.gems[0].get_revision(), None);
+        assert_eq!(result.git_gems.len(), 1);
+        assert_eq!(
+            result.git_gems[0].get_source(),
+            ("annotate", Some("3.2.0"), None)
+        );
+        assert_eq!(
+            result.git_gems[0].get_remote(),
+            Some("https://localgit.org/ruby/github_ctran_annotate_models.git")
+        );
+        assert_eq!(
+            result.git_gems[0].get_revision(),
+            Some("984c33e567c17fe6101ba367c880c7494c614561")
+        );
+        assert_eq!(
+            result.git_gems[0].get_dependencies(),
+            ["activerecord", "rake"]
+        );
+    }
 
-        let result = parse_gemfile(String::from(gemfile), false);
+    #[test]
+    fn test_when_contains_some_gems() {
+        let result = parse_gemfile(String::from(SOME_GEMS_FIXTURE));
         let gems = result.gems;
 
         assert_eq!(gems.len(), 6);
         assert_eq!(
             gems.first().unwrap().get_source(),
-            ("actioncable", "7.0.8.4", None)
+            ("actioncable", Some("7.0.8.4"), None)
+        );
+        assert_eq!(
+            gems.first().unwrap().get_dependencies(),
+            ["actionpack", "activesupport", "nio4r", "websocket-driver"]
+        );
+        assert!(gems.get(1).unwrap().get_dependencies().is_empty());
+        assert_eq!(
+            gems.get(1).unwrap().get_source(),
+            ("choice", Some("0.2.0"), None)
         );
-        assert_eq!(gems.get(1).unwrap().get_source(), ("choice", "0.2.0", None));
         assert_eq!(
             gems.get(2).unwrap().get_source(),
-            ("clavius", "1.0.4", None)
+            ("clavius", Some("1.0.4"), None)
         );
         assert_eq!(
             gems.get(3).unwrap().get_source(),
-            ("coderay", "1.1.3", None)
+            ("coderay", Some("1.1.3"), None)
         );
         assert_eq!(
             gems.get(4).unwrap().get_source(),
-            ("nokogiri", "1.16.5", Some("arm64-darwin"))
+            ("nokogiri", Some("1.16.5"), Some("arm64-darwin"))
         );
         assert_eq!(
             gems.get(5).unwrap().get_source(),
-            ("opentelemetry-instrumentation-net_http", "0.20.0", None)
+            (
+                "opentelemetry-instrumentation-net_http",
+                Some("0.20.0"),
+                None
+            )
+        );
+        assert!(gems
+            .iter()
+            .all(|gem| gem.get_remote() == Some("https://rubygems.org/")));
+    }
+
+    #[test]
+    fn test_environment() {
+        let result = parse_gemfile(String::from(SOME_GEMS_FIXTURE));
+
+        assert_eq!(
+            result.environment,
+            Environment {
+                ruby_version: Some(String::from("ruby 3.3.0p0")),
+                bundler_version: Some(String::from("2.5.9")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_when_crlf_and_byte_order_mark() {
+        let gemfile = format!(
+            "{BYTE_ORDER_MARK}{}",
+            SOME_GEMS_FIXTURE.trim_start().replace('\n', "\r\n")
+        );
+
+        let result = parse_gemfile(gemfile);
+        let names: Vec<&str> = result.gems.iter().map(|gem| gem.get_source().0).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "actioncable",
+                "choice",
+                "clavius",
+                "coderay",
+                "nokogiri",
+                "opentelemetry-instrumentation-net_http"
+            ]
         );
+        assert_eq!(result.git_gems.len(), 1);
+        assert_eq!(result.gems[0].get_remote(), Some("https://rubygems.org/"));
+        assert_eq!(result.direct.len(), 4);
+    }
+
+    #[test]
+    fn test_when_remote_after_specs() {
+        let gemfile = r#"
+GEM
+  specs:
+    choice (0.2.0)
+    coderay (1.1.3)
+  remote: https://gems.mycompany.com/
+
+PLATFORMS
+  x86_64-linux
+"#;
+
+        let result = parse_gemfile(String::from(gemfile));
+        let gems = result.gems;
+
+        assert_eq!(gems.len(), 2);
+        assert_eq!(gems[0].get_source(), ("choice", Some("0.2.0"), None));
+        assert_eq!(gems[0].get_remote(), Some("https://gems.mycompany.com/"));
+        assert_eq!(gems[1].get_remote(), Some("https://gems.mycompany.com/"));
+    }
+
+    #[test]
+    fn test_when_multiple_gem_sections() {
+        let gemfile = r#"
+GEM
+  remote: https://rubygems.org/
+  specs:
+    choice (0.2.0)
+
+GEM
+  specs:
+    private_gem (1.0.0)
+  remote: https://gems.mycompany.com/
+
+PLATFORMS
+  x86_64-linux
+"#;
+
+        let result = parse_gemfile(String::from(gemfile));
+        let gems = result.gems;
+
+        assert_eq!(gems.len(), 2);
+        assert_eq!(gems[0].get_source(), ("choice", Some("0.2.0"), None));
+        assert_eq!(gems[0].get_remote(), Some("https://rubygems.org/"));
+        assert_eq!(gems[1].get_source(), ("private_gem", Some("1.0.0"), None));
+        assert_eq!(gems[1].get_remote(), Some("https://gems.mycompany.com/"));
+        assert_eq!(
+            gems[1].get_remote_host().as_deref(),
+            Some("gems.mycompany.com")
+        );
+    }
+
+    #[test]
+    fn test_find_denied_sources() {
+        let gemfile = r#"
+GEM
+  remote: https://rubygems.org/
+  specs:
+    choice (0.2.0)
+
+GEM
+  remote: https://untrusted-mirror.example.com/
+  specs:
+    private_gem (1.0.0)
+
+PLATFORMS
+  x86_64-linux
+"#;
+
+        let result = parse_gemfile(String::from(gemfile));
+
+        assert_eq!(
+            result.find_denied_sources(&[String::from("Untrusted-Mirror.example.com")]),
+            vec![String::from(
+                "private_gem 1.0.0 (https://untrusted-mirror.example.com/)"
+            )]
+        );
+        assert!(result
+            .find_denied_sources(&[String::from("gems.mycompany.com")])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_find_denied_sources_in_git_section() {
+        let gemfile = r#"
+GIT
+  remote: https://untrusted-git.example.com/ruby/annotate_models.git
+  revision: 984c33e567c17fe6101ba367c880c7494c614561
+  specs:
+    annotate (3.2.0)
+
+GIT
+  remote: git@private-git.example.com:ruby/rubocop.git
+  revision: 6c3a3c6ed3e3a87a8bd2de4a0b0ec5d2e1c1b1b5
+  specs:
+    rubocop (1.60.0)
+
+GEM
+  remote: https://rubygems.org/
+  specs:
+    rake (13.0.6)
+
+PLATFORMS
+  x86_64-linux
+"#;
+
+        let result = parse_gemfile(String::from(gemfile));
+
+        assert_eq!(
+            result.find_denied_sources(&[
+                String::from("untrusted-git.example.com"),
+                String::from("private-git.example.com"),
+            ]),
+            vec![
+                String::from(
+                    "annotate 3.2.0 (https://untrusted-git.example.com/ruby/annotate_models.git)"
+                ),
+                String::from("rubocop 1.60.0 (git@private-git.example.com:ruby/rubocop.git)"),
+            ]
+        );
+        assert!(result
+            .find_denied_sources(&[String::from("rubygems.org.example.com")])
+            .is_empty());
+    }
+
+    const PLATFORM_VARIANTS: &str = r#"
+GEM
+  remote: https://rubygems.org/
+  specs:
+    nokogiri (1.16.5-arm64-darwin)
+    nokogiri (1.16.5-x86_64-linux)
+    rake (13.0.6)
+
+GEM
+  remote: https://gems.mycompany.com/
+  specs:
+    nokogiri (1.16.5-x86_64-linux)
+    rake (13.0.6)
+
+PLATFORMS
+  arm64-darwin
+  x86_64-linux
+"#;
+
+    #[test]
+    fn test_filter_names() {
+        let mut result = parse_gemfile(String::from(SOME_GEMS_FIXTURE));
+
+        result.filter_names(&Regex::new("^(action|co)").unwrap());
+        let names: Vec<&str> = result.gems.iter().map(|gem| gem.get_source().0).collect();
+
+        assert_eq!(names, vec!["actioncable", "coderay"]);
+        assert!(result.git_gems.is_empty());
+    }
+
+    #[test]
+    fn test_dedupe_by_purl() {
+        let mut result = parse_gemfile(String::from(PLATFORM_VARIANTS));
+
+        result.dedupe(DedupeKey::Purl);
+
+        assert_eq!(result.gems.len(), 3);
+        assert_eq!(
+            result.gems[0].get_source(),
+            ("nokogiri", Some("1.16.5"), Some("arm64-darwin"))
+        );
+        assert_eq!(
+            result.gems[1].get_source(),
+            ("nokogiri", Some("1.16.5"), Some("x86_64-linux"))
+        );
+        assert_eq!(result.gems[2].get_source(), ("rake", Some("13.0.6"), None));
+        assert_eq!(result.gems[2].get_remote(), Some("https://rubygems.org/"));
+    }
+
+    #[test]
+    fn test_dedupe_by_name_version() {
+        let mut result = parse_gemfile(String::from(PLATFORM_VARIANTS));
+
+        result.dedupe(DedupeKey::NameVersion);
+
+        assert_eq!(result.gems.len(), 2);
+        assert_eq!(
+            result.gems[0].get_source(),
+            ("nokogiri", Some("1.16.5"), Some("arm64-darwin"))
+        );
+        assert_eq!(result.gems[1].get_source(), ("rake", Some("13.0.6"), None));
+    }
+
+    #[test]
+    fn test_without_dedupe() {
+        let result = parse_gemfile(String::from(PLATFORM_VARIANTS));
+
+        assert_eq!(result.gems.len(), 5);
     }
 }
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use futures::lock::Mutex as AsyncMutex;
+
+///
+/// Versions response fetched in current run. Empty until the first
+/// request for gem succeeds, concurrent requests wait for it
+///
+pub(crate) type FetchedResponse = Arc<AsyncMutex<Option<String>>>;
+
+///
+/// Local directory with rubygems.org versions responses. Every file
+/// is named `<gem name>.json` and contains response body as is
+///
+#[derive(Debug, Default)]
+pub(crate) struct Cache {
+    // Cache directory, cache disabled if not set
+    dir: Option<PathBuf>,
+
+    // Never make requests, only cache is used
+    offline: bool,
+
+    // Refresh responses served from cache after bom file is written
+    refresh: bool,
+
+    // Names of gems, which were served from cache
+    served: Mutex<Vec<String>>,
+
+    // Versions responses fetched in current run, by cache key
+    fetched: Mutex<HashMap<String, FetchedResponse>>,
+}
+
+impl Cache {
+    ///
+    /// Initializes new Cache instance
+    ///
+    pub(crate) fn new(dir: Option<PathBuf>, offline: bool) -> Self {
+        Cache {
+            dir,
+            offline,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Enables refreshing of responses served from cache
+    ///
+    pub(crate) fn with_refresh(self, refresh: bool) -> Self {
+        Cache { refresh, ..self }
+    }
+
+    ///
+    /// Remembers gem served from cache, if it should be refreshed later
+    ///
+    pub(crate) fn mark_served(&self, name: &str) {
+        if self.refresh {
+            self.served.lock().unwrap().push(name.to_string());
+        }
+    }
+
+    ///
+    /// Returns names of gems, which cached responses should be refreshed
+    ///
+    pub(crate) fn get_served(&self) -> Vec<String> {
+        let mut served = self.served.lock().unwrap().clone();
+        served.sort();
+        served.dedup();
+
+        served
+    }
+
+    ///
+    /// Returns versions response of gem fetched in current run, so one
+    /// request serves all versions and platforms of gem
+    ///
+    pub(crate) fn get_fetched(&self, name: &str) -> FetchedResponse {
+        self.fetched
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    ///
+    /// Check if network requests are forbidden
+    ///
+    pub(crate) fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    ///
+    /// Try to read cached versions response for gem
+    ///
+    pub(crate) fn read(&self, name: &str) -> Option<String> {
+        let path = self.get_path(name)?;
+
+        fs::read_to_string(path).ok()
+    }
+
+    ///
+    /// Saves versions response for gem. Cache is optional, so write
+    /// errors are ignored
+    ///
+    pub(crate) fn write(&self, name: &str, content: &str) {
+        if let Some(path) = self.get_path(name) {
+            let _ = fs::write(path, content);
+        }
+    }
+
+    //
+    // Builds cache file path for gem. Names which could point outside
+    // cache directory are rejected
+    //
+    fn get_path(&self, name: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return None;
+        }
+
+        Some(dir.join(format!("{name}.json")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    fn cache_dir(test_name: &str) -> PathBuf {
+        let dir = temp_dir().join(format!("cyclonedx-rs-gem-cache-{test_name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_read_write() {
+        let cache = Cache::new(Some(cache_dir("read-write")), false);
+
+        cache.write("rails", "[]");
+
+        assert_eq!(cache.read("rails"), Some(String::from("[]")));
+        assert_eq!(cache.read("rack"), None);
+    }
+
+    #[test]
+    fn test_when_cache_disabled() {
+        let cache = Cache::default();
+
+        cache.write("rails", "[]");
+
+        assert_eq!(cache.read("rails"), None);
+        assert!(!cache.is_offline());
+    }
+
+    #[test]
+    fn test_mark_served() {
+        let cache = Cache::new(Some(cache_dir("served")), false);
+        let refreshed = Cache::new(Some(cache_dir("served")), false).with_refresh(true);
+
+        for name in ["rails", "rack", "rails"] {
+            cache.mark_served(name);
+            refreshed.mark_served(name);
+        }
+
+        assert!(cache.get_served().is_empty());
+        assert_eq!(refreshed.get_served(), vec!["rack", "rails"]);
+    }
+
+    #[test]
+    fn test_get_fetched() {
+        let cache = Cache::default();
+
+        *cache.get_fetched("rails").try_lock().unwrap() = Some(String::from("[]"));
+
+        assert_eq!(
+            cache.get_fetched("rails").try_lock().unwrap().as_deref(),
+            Some("[]")
+        );
+        assert_eq!(*cache.get_fetched("rack").try_lock().unwrap(), None);
+    }
+
+    #[test]
+    fn test_rejects_unsafe_names() {
+        let cache = Cache::new(Some(cache_dir("unsafe")), true);
+
+        assert_eq!(cache.get_path("../rails"), None);
+        assert_eq!(cache.get_path("gems/rails"), None);
+        assert_eq!(cache.get_path(""), None);
+        assert!(cache.get_path("rails").is_some());
+    }
+}
@@ -1,22 +1,70 @@
 use anyhow::Result;
-use reqwest::{Client, Response};
+use reqwest::{Client, ClientBuilder as HttpClientBuilder, NoProxy, Proxy, Response};
 use reqwest_middleware::ClientWithMiddleware;
 use reqwest_middleware::{ClientBuilder, Result as MiddlewareResult};
 use reqwest_retry::{
     default_on_request_failure, policies::ExponentialBackoff, RetryTransientMiddleware, Retryable,
     RetryableStrategy,
 };
+use std::time::Duration;
+use url::Url;
+
+mod attempts;
+mod rate_limit;
+mod trace;
+
+pub(crate) use attempts::Attempts;
+use attempts::CountAttempts;
+use rate_limit::RetryRateLimited;
+use trace::TraceRequests;
+
+///
+/// Http clients settings shared by rubygems.org and Nexus clients
+///
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ClientOptions {
+    // Proxy for all requests. If not set, HTTP_PROXY/HTTPS_PROXY
+    // environment variables are used
+    pub(crate) proxy: Option<Url>,
+    // Total timeout for each gems registry request. No timeout if not set
+    pub(crate) registry_timeout: Option<Duration>,
+    // Total timeout for each Nexus request. No timeout if not set
+    pub(crate) nexus_timeout: Option<Duration>,
+    // Timeout for connection establishing only
+    pub(crate) connect_timeout: Option<Duration>,
+    // Number of retries for failed requests. If not set, each client
+    // uses its own default
+    pub(crate) retries: Option<u32>,
+    // Log every request and response to stderr
+    pub(crate) trace_requests: bool,
+}
+
+const REGISTRY_RETRIES: u32 = 3;
+const NEXUS_RETRIES: u32 = 5;
+
+impl ClientOptions {
+    ///
+    /// Returns number of retries for gems registry requests
+    ///
+    pub(crate) fn registry_retries(&self) -> u32 {
+        self.retries.unwrap_or(REGISTRY_RETRIES)
+    }
+}
 
 ///
 /// Strategy for retry all failed requests, except 404
-/// (gem not found)
+/// (gem not found) and 429, which are repeated after delay
+/// requested by server
 ///
 struct RetryAllExcept404;
 impl RetryableStrategy for RetryAllExcept404 {
     fn handle(&self, res: &MiddlewareResult<Response>) -> Option<Retryable> {
         match res {
+            Ok(success) if success.status().is_success() => None,
             // don't repeat if gem not found
             Ok(success) if success.status() == 404 => None,
+            // repeated by RetryRateLimited
+            Ok(success) if success.status() == 429 => None,
             Ok(_) => Some(Retryable::Transient),
             Err(error) => default_on_request_failure(error),
         }
@@ -26,27 +74,158 @@ impl RetryableStrategy for RetryAllExcept404 {
 ///
 /// Configure reqwest http client with custom retry strategy
 ///
-pub(crate) fn get_client() -> Result<ClientWithMiddleware> {
-    let http = Client::builder()
+pub(crate) fn get_client(options: &ClientOptions) -> Result<ClientWithMiddleware> {
+    let http = configure(Client::builder(), options, options.registry_timeout)?
         .redirect(reqwest::redirect::Policy::none())
         .build()?;
-    let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
-    let retry_middleware =
-        RetryTransientMiddleware::new_with_policy_and_strategy(retry_policy, RetryAllExcept404);
-    let client = ClientBuilder::new(http).with(retry_middleware).build();
+    let retries = options.registry_retries();
+    let mut builder = ClientBuilder::new(http);
+    if retries > 0 {
+        builder = builder.with(RetryTransientMiddleware::new_with_policy_and_strategy(
+            retry_policy(retries),
+            RetryAllExcept404,
+        ));
+        builder = builder.with(RetryRateLimited::new(retries));
+    }
+    builder = builder.with(CountAttempts);
+    if options.trace_requests {
+        builder = builder.with(TraceRequests::default());
+    }
 
-    Ok(client)
+    Ok(builder.build())
 }
 
 ///
 /// Configure reqwest http client for nexus requests
 ///
-pub(crate) fn get_nexus_client() -> Result<ClientWithMiddleware> {
-    let http = Client::builder().build()?;
-    let retry_policy = ExponentialBackoff::builder().build_with_max_retries(5);
-    let client = ClientBuilder::new(http)
-        .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-        .build();
+pub(crate) fn get_nexus_client(options: &ClientOptions) -> Result<ClientWithMiddleware> {
+    let http = configure(Client::builder(), options, options.nexus_timeout)?.build()?;
+    let retries = options.retries.unwrap_or(NEXUS_RETRIES);
+    let mut builder = ClientBuilder::new(http);
+    if retries > 0 {
+        builder = builder.with(RetryTransientMiddleware::new_with_policy(retry_policy(
+            retries,
+        )));
+    }
+    if options.trace_requests {
+        builder = builder.with(TraceRequests::default());
+    }
+
+    Ok(builder.build())
+}
+
+//
+// Builds exponential backoff policy with given retries count
+//
+fn retry_policy(retries: u32) -> ExponentialBackoff {
+    ExponentialBackoff::builder().build_with_max_retries(retries)
+}
 
-    Ok(client)
+//
+// Applies common settings and client specific timeout to http client
+// builder. Explicit proxy takes precedence over HTTP_PROXY/HTTPS_PROXY,
+// but NO_PROXY is still respected
+//
+fn configure(
+    builder: HttpClientBuilder,
+    options: &ClientOptions,
+    timeout: Option<Duration>,
+) -> Result<HttpClientBuilder> {
+    let builder = match &options.proxy {
+        Some(proxy) => builder.proxy(Proxy::all(proxy.as_str())?.no_proxy(NoProxy::from_env())),
+        None => builder,
+    };
+    let builder = match timeout {
+        Some(timeout) => builder.timeout(timeout),
+        None => builder,
+    };
+    let builder = match options.connect_timeout {
+        Some(timeout) => builder.connect_timeout(timeout),
+        None => builder,
+    };
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clients_with_proxy() {
+        let options = ClientOptions {
+            proxy: Some(Url::parse("http://proxy.mycompany.com:3128").unwrap()),
+            ..Default::default()
+        };
+
+        assert!(get_client(&options).is_ok());
+        assert!(get_nexus_client(&options).is_ok());
+    }
+
+    #[test]
+    fn test_clients_with_connect_timeout() {
+        let options = ClientOptions {
+            connect_timeout: Some(Duration::from_secs(3)),
+            ..Default::default()
+        };
+
+        assert!(get_client(&options).is_ok());
+        assert!(get_nexus_client(&options).is_ok());
+    }
+
+    #[test]
+    fn test_clients_with_separate_timeouts() {
+        let options = ClientOptions {
+            registry_timeout: Some(Duration::from_secs(5)),
+            nexus_timeout: None,
+            ..Default::default()
+        };
+
+        assert!(get_client(&options).is_ok());
+        assert!(get_nexus_client(&options).is_ok());
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        assert_eq!(retry_policy(0).max_n_retries, Some(0));
+        assert_eq!(retry_policy(7).max_n_retries, Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_count_attempts() {
+        let server = crate::test_support::MockServer::new()
+            .route("/api/v1/versions/rake.json", 500, "")
+            .start();
+        let policy = ExponentialBackoff::builder()
+            .retry_bounds(Duration::from_millis(1), Duration::from_millis(1))
+            .build_with_max_retries(2);
+        let client = ClientBuilder::new(Client::new())
+            .with(RetryTransientMiddleware::new_with_policy_and_strategy(
+                policy,
+                RetryAllExcept404,
+            ))
+            .with(CountAttempts)
+            .build();
+
+        let response = client
+            .get(server.url().join("api/v1/versions/rake.json").unwrap())
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 500);
+        assert_eq!(Attempts::of(&response), 3);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_clients_without_retries() {
+        let options = ClientOptions {
+            retries: Some(0),
+            ..Default::default()
+        };
+
+        assert!(get_client(&options).is_ok());
+        assert!(get_nexus_client(&options).is_ok());
+    }
 }
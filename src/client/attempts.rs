@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+
+///
+/// Number of attempts made to get response, including retries
+///
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Attempts(pub(crate) u32);
+
+impl Attempts {
+    ///
+    /// Returns number of attempts recorded in response, zero for responses
+    /// of clients without attempts counting
+    ///
+    pub(crate) fn of(response: &Response) -> u32 {
+        response
+            .extensions()
+            .get::<Attempts>()
+            .map_or(0, |attempts| attempts.0)
+    }
+}
+
+///
+/// Middleware, which counts attempts made by retry middlewares. It should
+/// be added after them, so every repeated request passes through it.
+/// Request extensions are shared between attempts, so counter is kept
+/// there and copied to response
+///
+pub(crate) struct CountAttempts;
+
+#[async_trait]
+impl Middleware for CountAttempts {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let attempts = Attempts(extensions.get::<Attempts>().map_or(0, |a| a.0) + 1);
+        extensions.insert(attempts);
+
+        let mut response = next.run(req, extensions).await?;
+        response.extensions_mut().insert(attempts);
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next, Result};
+
+// Used when Retry-After header is missing or is not a number of seconds
+const DEFAULT_DELAY: Duration = Duration::from_secs(1);
+// Longer delays are cut, so a single gem could not stall the whole run
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+///
+/// Middleware, which repeats requests rejected with 429 Too Many Requests
+/// after delay requested by server in Retry-After header
+///
+pub(crate) struct RetryRateLimited {
+    retries: u32,
+}
+
+impl RetryRateLimited {
+    pub(crate) fn new(retries: u32) -> Self {
+        RetryRateLimited { retries }
+    }
+}
+
+#[async_trait]
+impl Middleware for RetryRateLimited {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let mut attempt = 0;
+
+        loop {
+            // requests with streaming body could not be repeated
+            let Some(request) = req.try_clone() else {
+                return next.run(req, extensions).await;
+            };
+
+            let response = next.clone().run(request, extensions).await?;
+            if response.status() != StatusCode::TOO_MANY_REQUESTS || attempt >= self.retries {
+                return Ok(response);
+            }
+
+            let delay = retry_after(&response);
+            log::debug!(
+                target: "rate_limited",
+                "Rate limited by {}, retrying in {}s",
+                response.url(),
+                delay.as_secs()
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+//
+// Reads delay from Retry-After header in seconds form. HTTP date form
+// is not supported, default delay is used for it
+//
+fn retry_after(response: &Response) -> Duration {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map_or(DEFAULT_DELAY, Duration::from_secs)
+        .min(MAX_DELAY)
+}
@@ -1,22 +1,43 @@
+use std::env;
 use std::env::current_dir;
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use clap::{Parser, ValueEnum};
+use clap::{ArgGroup, Parser, ValueEnum};
+use regex::Regex;
+use url::Url;
 
-#[derive(Parser, Debug)]
+use crate::archive;
+use crate::bom_se;
+use crate::bundler;
+use crate::client::ClientOptions;
+use crate::gem;
+use crate::logger;
+use crate::nexus;
+use crate::progress::ProgressMode;
+use crate::stale;
+use crate::timestamp;
+
+const DEFAULT_REGISTRY_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_NEXUS_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Parser, Debug, Default)]
 #[command(version, about, long_about = None)]
+#[command(group(ArgGroup::new("repository").args(["nexus_url", "artifactory_url"])))]
 struct Args {
     // Path to directory containing Gemfile.lock file. If path not set, it
-    // try to find Gemfile.lock in current directory
+    // try to find Gemfile.lock in current directory. With `-` Gemfile.lock
+    // content is read from stdin
     #[arg(short, long)]
     path: Option<String>,
 
-    // Path to output the bom file to
+    // Path to output the bom file to. Path with .json or .xml extension
+    // is treated as bom file name, otherwise as directory
     #[arg(short, long)]
     output: Option<String>,
 
-    // Output bom file format (json or xml)
+    // Output bom file format (json, xml, yaml or protobuf)
     #[arg(short, long, value_enum, default_value_t=Format::Json)]
     format_file: Format,
 
@@ -24,9 +45,287 @@ struct Args {
     #[arg(short, long)]
     verbose: bool,
 
+    // Run verbosely, printing diagnostics as JSON lines to stderr
+    #[arg(long)]
+    verbose_json: bool,
+
+    // Print only errors
+    #[arg(short, long, conflicts_with_all = ["verbose", "verbose_json"])]
+    quiet: bool,
+
+    // Show progress bar while fetching gems info. In auto mode it is shown
+    // only when stdout is a terminal and quiet mode is off
+    #[arg(long, value_enum, default_value_t, value_name = "WHEN")]
+    progress: ProgressMode,
+
     // Remote nexus repository url
     #[arg(short, long)]
     nexus_url: Option<String>,
+
+    // Exit with non-zero code when bom file would not contain any component
+    // or Gemfile.lock has no gems
+    #[arg(long)]
+    fail_on_empty: bool,
+
+    // Bom creation time in RFC 3339 format, SOURCE_DATE_EPOCH environment
+    // variable or current time is used when not set
+    #[arg(long, value_name = "RFC3339", value_parser = timestamp::parse_rfc3339)]
+    timestamp: Option<u64>,
+
+    // Mark gems released earlier than given duration (90d, 12w, 6m, 2y) as stale
+    #[arg(long, value_parser = stale::parse_max_age)]
+    flag_older_than: Option<i64>,
+
+    // Exit with non-zero code when bom file contains stale gems
+    #[arg(long)]
+    fail_on_stale: bool,
+
+    // Exit with non-zero code when info about some gems could not be fetched
+    #[arg(long)]
+    fail_on_error: bool,
+
+    // Directory for caching rubygems.org responses
+    #[arg(long)]
+    cache_dir: Option<String>,
+
+    // Use only cached responses, never make requests to rubygems.org
+    #[arg(long, requires = "cache_dir")]
+    offline: bool,
+
+    // Use cached responses even if they are outdated, cached responses
+    // are refreshed after bom file is written
+    #[arg(long, requires = "cache_dir", conflicts_with = "offline")]
+    prefer_cache: bool,
+
+    // Collapse newlines and repeated spaces in components descriptions
+    #[arg(long)]
+    normalize_whitespace_in_description: bool,
+
+    // Gems registry url with rubygems.org compatible API
+    #[arg(long, value_parser = parse_url, default_value = gem::DEFAULT_REGISTRY_URL)]
+    registry_url: Option<Url>,
+
+    // Bearer token for private gems registry. If not set, bundler style
+    // BUNDLE_<HOST> environment variable is used
+    #[arg(long)]
+    registry_token: Option<String>,
+
+    // Keep only gems from given Gemfile groups and their dependencies.
+    // Comma separated or repeated, requires Gemfile next to Gemfile.lock
+    #[arg(long, value_name = "GROUP", value_delimiter = ',')]
+    only_groups: Vec<String>,
+
+    // Skip gems, which belong only to given Gemfile groups, and their
+    // dependencies. Comma separated or repeated, requires Gemfile next
+    // to Gemfile.lock
+    #[arg(long, value_name = "GROUP", value_delimiter = ',')]
+    exclude_groups: Vec<String>,
+
+    // Include gems from development and test Gemfile groups, they are
+    // skipped by default when Gemfile is found next to Gemfile.lock
+    #[arg(long)]
+    include_dev_dependencies: bool,
+
+    // Fail if any gem comes from given source host. Could be repeated
+    #[arg(long = "deny-source", value_name = "HOST")]
+    deny_sources: Vec<String>,
+
+    // Proxy for rubygems.org and Nexus requests. Takes precedence over
+    // HTTP_PROXY/HTTPS_PROXY environment variables
+    #[arg(long, value_parser = parse_url)]
+    proxy: Option<Url>,
+
+    // Timeout in seconds for each request to rubygems.org and Nexus,
+    // 0 disables timeout. By default 30 for gems registry and 60 for Nexus
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    // Timeout in seconds for each gems registry request, overrides
+    // --timeout. 0 disables timeout
+    #[arg(long, value_name = "SECONDS")]
+    registry_timeout: Option<u64>,
+
+    // Timeout in seconds for each Nexus request, overrides --timeout.
+    // 0 disables timeout
+    #[arg(long, value_name = "SECONDS")]
+    nexus_timeout: Option<u64>,
+
+    // Timeout in seconds for connection establishing (including TLS
+    // handshake) to rubygems.org and Nexus, independent of --timeout
+    #[arg(long, value_name = "SECONDS")]
+    connect_timeout: Option<u64>,
+
+    // Number of retries for failed requests, 0 disables retries.
+    // By default 3 for gems registry and 5 for Nexus
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+
+    // Replace extension of output file name, if it does not match format
+    #[arg(long)]
+    fix_extension: bool,
+
+    // Remove repeated gems from bom file, comparing them by purl or
+    // by name and version ignoring platform
+    #[arg(long, value_enum, value_name = "KEY")]
+    components_dedupe_by: Option<bundler::DedupeKey>,
+
+    // Order of requests to gems registry, useful for partial results
+    // of very large Gemfile.lock
+    #[arg(long, value_enum, default_value_t, value_name = "ORDER")]
+    fetch_order: bundler::FetchOrder,
+
+    // Add bom level annotation with given text, could be repeated
+    #[arg(long = "annotation", value_name = "TEXT")]
+    annotations: Vec<String>,
+
+    // Exit with non-zero code if some packages are not found in Nexus
+    // or Artifactory
+    #[arg(long, requires = "repository")]
+    fail_on_missing: bool,
+
+    // Log every http request url, response status, timing and beginning
+    // of response body to stderr
+    #[arg(long)]
+    trace_requests: bool,
+
+    // Set components group to host of gem source, e.g. rubygems.org
+    #[arg(long)]
+    component_group_from_source: bool,
+
+    // Spread start of the first requests over given number of
+    // milliseconds to avoid burst of connections
+    #[arg(long, value_name = "MILLISECONDS", default_value_t = 0)]
+    concurrency_warmup: u64,
+
+    // User name for Nexus basic authentication
+    #[arg(long, requires = "nexus_url")]
+    nexus_user: Option<String>,
+
+    // Password for Nexus basic authentication
+    #[arg(long, requires = "nexus_user")]
+    nexus_password: Option<String>,
+
+    // Bearer token for Nexus authentication
+    #[arg(long, requires = "nexus_url", conflicts_with = "nexus_user")]
+    nexus_token: Option<String>,
+
+    // Artifactory base url, e.g. https://mycompany.jfrog.io/artifactory.
+    // Gems presence is checked with artifact quick search instead of Nexus
+    #[arg(long, conflicts_with = "nexus_url")]
+    artifactory_url: Option<String>,
+
+    // Artifactory repository to search gems in, all repositories by default
+    #[arg(long, value_name = "REPOSITORY", requires = "artifactory_url")]
+    artifactory_repo: Option<String>,
+
+    // Access token for Artifactory authentication
+    #[arg(long, requires = "artifactory_url")]
+    artifactory_token: Option<String>,
+
+    // Artefact format used in Nexus search requests
+    #[arg(long, requires = "nexus_url", default_value = nexus::DEFAULT_FORMAT)]
+    nexus_format: String,
+
+    // Exit with non-zero code if some generated purl is malformed,
+    // otherwise only print warning
+    #[arg(long)]
+    strict: bool,
+
+    // Record lockfile paths, where each gem was found, as component
+    // evidence occurrences
+    #[arg(long)]
+    component_evidence_occurrences: bool,
+
+    // Write packages not found in Nexus or Artifactory to json report
+    #[arg(long, value_name = "PATH", requires = "repository")]
+    nexus_report: Option<PathBuf>,
+
+    // Include found packages to json report too
+    #[arg(long, requires = "nexus_report")]
+    nexus_report_all: bool,
+
+    // Write gems, which info could not be fetched, to json report.
+    // Report is written on successful runs too
+    #[arg(long, value_name = "PATH")]
+    error_report: Option<PathBuf>,
+
+    // Resolve versions and checksums with lighter compact index
+    // `/info/<gem>` endpoint. It has no gems metadata (description,
+    // authors, licenses)
+    #[arg(long)]
+    rubygems_info_endpoint: bool,
+
+    // Resolve platform specific gems for given platform (x86_64-linux,
+    // java, etc) instead of platform from Gemfile.lock. Pure ruby build
+    // is used, when gem is not published for the platform
+    #[arg(long, value_name = "PLATFORM")]
+    target_platform: Option<String>,
+
+    // Include gems listed without version in Gemfile.lock, their latest
+    // release is used
+    #[arg(long)]
+    include_versionless: bool,
+
+    // Add minimum depth of gem in dependency tree as component property,
+    // gems declared in Gemfile have depth 1
+    #[arg(long)]
+    component_depth: bool,
+
+    // Octal mode of written bom file, e.g. 644. By default permissions of
+    // replaced file are kept, new file gets permissions derived from umask
+    #[arg(long, value_name = "MODE", value_parser = parse_mode)]
+    output_mode: Option<u32>,
+
+    // Keep only gems, which names match given regular expression
+    #[arg(long, alias = "name-filter", value_name = "REGEX", value_parser = parse_regex)]
+    components_name_filter: Option<Regex>,
+
+    // Add top level string field to json bom file (metadata property for
+    // xml), could be repeated. Standard bom fields could not be replaced
+    #[arg(long = "extra-bom-field", value_name = "KEY=VALUE", value_parser = parse_extra_field)]
+    extra_bom_fields: Vec<(String, String)>,
+
+    // Add project as bom metadata component of given type, component
+    // name is the name of project directory
+    #[arg(long, value_enum, value_name = "TYPE")]
+    root_type: Option<bom_se::ComponentType>,
+
+    // Type of all gem components
+    #[arg(long, value_enum, value_name = "TYPE", default_value_t = bom_se::ComponentType::Library)]
+    component_type: bom_se::ComponentType,
+
+    // Find all Gemfile.lock files in subdirectories of input directory
+    #[arg(long)]
+    recursive: bool,
+
+    // Create one merged bom file or bom file next to each found Gemfile.lock
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        value_name = "MODE",
+        requires = "recursive"
+    )]
+    recursive_output: RecursiveOutput,
+
+    // Replace components descriptions matching given regular expression
+    // with placeholder
+    #[arg(long, alias = "redact-descriptions-matching", value_name = "REGEX", value_parser = parse_regex)]
+    redact_description_matching: Option<Regex>,
+
+    // Validate json bom file against CycloneDX schema before writing it
+    #[arg(long)]
+    validate: bool,
+
+    // Compress bom file with gzip, `.gz` is appended to output file name.
+    // Output file name ending with `.gz` enables compression as well
+    #[arg(long)]
+    compress: bool,
+
+    // Add `checksum` qualifier and, for non default registry,
+    // `repository_url` qualifier to gems purl and bom-ref
+    #[arg(long)]
+    purl_qualifiers: bool,
 }
 
 #[derive(Debug)]
@@ -35,16 +334,87 @@ pub(crate) struct Params {
     pub(crate) input_file_name: PathBuf,
     pub(crate) output_file_name: PathBuf,
     pub(crate) verbose: bool,
+    pub(crate) verbose_json: bool,
+    pub(crate) quiet: bool,
+    pub(crate) progress: bool,
     pub(crate) nexus_url: Option<String>,
+    pub(crate) fail_on_empty: bool,
+    pub(crate) max_age: Option<i64>,
+    pub(crate) fail_on_stale: bool,
+    pub(crate) fail_on_error: bool,
+    pub(crate) cache_dir: Option<PathBuf>,
+    pub(crate) offline: bool,
+    pub(crate) prefer_cache: bool,
+    pub(crate) timestamp: Option<u64>,
+    pub(crate) normalize_description: bool,
+    pub(crate) registry_url: Url,
+    pub(crate) registry_credentials: Option<gem::Credentials>,
+    pub(crate) deny_sources: Vec<String>,
+    pub(crate) only_groups: Vec<String>,
+    pub(crate) exclude_groups: Vec<String>,
+    pub(crate) include_dev_dependencies: bool,
+    pub(crate) client_options: ClientOptions,
+    pub(crate) dedupe_by: Option<bundler::DedupeKey>,
+    pub(crate) fetch_order: bundler::FetchOrder,
+    pub(crate) annotations: Vec<String>,
+    pub(crate) fail_on_missing: bool,
+    pub(crate) group_from_source: bool,
+    pub(crate) concurrency_warmup: Duration,
+    pub(crate) nexus_credentials: Option<gem::Credentials>,
+    pub(crate) nexus_format: String,
+    pub(crate) artifactory_url: Option<String>,
+    pub(crate) artifactory_repo: Option<String>,
+    pub(crate) artifactory_credentials: Option<gem::Credentials>,
+    pub(crate) strict: bool,
+    pub(crate) evidence_occurrences: bool,
+    pub(crate) nexus_report: Option<PathBuf>,
+    pub(crate) nexus_report_all: bool,
+    pub(crate) error_report: Option<PathBuf>,
+    pub(crate) compact_index: bool,
+    pub(crate) target_platform: Option<String>,
+    pub(crate) include_versionless: bool,
+    pub(crate) component_depth: bool,
+    pub(crate) output_mode: Option<u32>,
+    pub(crate) name_filter: Option<Regex>,
+    pub(crate) extra_bom_fields: Vec<(String, String)>,
+    pub(crate) root_type: Option<bom_se::ComponentType>,
+    pub(crate) component_type: bom_se::ComponentType,
+    pub(crate) recursive: bool,
+    pub(crate) recursive_output: RecursiveOutput,
+    pub(crate) redact_pattern: Option<Regex>,
+    pub(crate) validate: bool,
+    pub(crate) compress: bool,
+    pub(crate) purl_qualifiers: bool,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
-pub(crate) enum Format {
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Default)]
+pub enum Format {
     // xml output, creates bom.xml
     Xml,
 
     // json output, creates bom.json
+    #[default]
     Json,
+
+    // yaml output, creates bom.yaml
+    Yaml,
+
+    // CycloneDX protobuf output, creates bom.bin
+    Protobuf,
+}
+
+const STDIN_PATH: &str = "-";
+const GZIP_EXTENSION: &str = ".gz";
+const BOM_EXTENSIONS: &[&str] = &["json", "xml", "yaml", "yml", "bin"];
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug, Default)]
+pub(crate) enum RecursiveOutput {
+    // one bom file for all Gemfile.lock files
+    #[default]
+    Merged,
+
+    // bom file next to each Gemfile.lock
+    PerFile,
 }
 
 ///
@@ -54,61 +424,320 @@ pub(super) fn prepare_env() -> Params {
     let args = Args::parse();
     let cwd = current_dir().unwrap();
 
-    let params = parse_params(args, cwd);
+    let mut params = parse_params(args, cwd);
+
+    if params.registry_credentials.is_none() {
+        params.registry_credentials = bundler_env_credentials(&params.registry_url);
+    }
+
+    let env_level = env::var("RUST_LOG").ok();
+    logger::init(
+        logger::level(params.verbose, params.quiet, env_level.as_deref()),
+        params.verbose_json,
+    );
 
-    if params.verbose {
-        print_params(&params);
+    if has_extension_mismatch(&params.output_file_name, &params.format) {
+        log::warn!(
+            target: "extension_mismatch",
+            "bom file {} extension does not match {} format, use --fix-extension to correct it",
+            params.output_file_name.display(),
+            params.format
+        );
     }
 
+    print_params(&params);
+
     params
 }
 
+///
+/// Builds params from given command line arguments, for tests of other
+/// modules
+///
+#[cfg(test)]
+pub(crate) fn parse_test_params(args: &[&str]) -> Params {
+    let args = std::iter::once("cyclonedx-rs-gem").chain(args.iter().copied());
+
+    parse_params(Args::parse_from(args), PathBuf::from("/tmp/project"))
+}
+
 //
 // Implements logic for arguments parsing
 //
 fn parse_params(args: Args, cwd: PathBuf) -> Params {
     let lock_file_name = "Gemfile.lock";
-    let bom_file_name = format!("bom.{}", &args.format_file);
+    let bom_file_name = format!("bom.{}", args.format_file.extension());
 
     let mut input_path = match args.path {
         Some(path_str) => PathBuf::from(path_str),
-        None => cwd,
+        None => cwd.clone(),
     };
+    let is_stdin = is_stdin_path(&input_path);
 
-    let mut output_path = match args.output {
-        Some(path_str) => PathBuf::from(path_str),
-        None => input_path.clone(),
+    // bom file for stdin input is written to current directory, for
+    // archive input next to archive
+    let archive = archive::split_archive_path(&input_path.join(lock_file_name));
+    let mut output_path = match (args.output, archive) {
+        (Some(path_str), _) => PathBuf::from(path_str),
+        (None, _) if is_stdin => cwd,
+        (None, Some((archive, _))) => archive
+            .parent()
+            .map_or_else(|| cwd.clone(), Path::to_path_buf),
+        (None, None) => input_path.clone(),
     };
 
-    input_path.push(lock_file_name);
-    output_path.push(bom_file_name);
+    if !is_stdin {
+        input_path.push(lock_file_name);
+    }
+
+    // `.gz` suffix is removed, so bom file name checks see real extension
+    let is_gzip_output = has_gzip_extension(&output_path);
+    if is_gzip_output {
+        output_path.set_extension("");
+    }
+    if !is_bom_file_name(&output_path) {
+        output_path.push(bom_file_name);
+    } else if args.fix_extension {
+        output_path.set_extension(args.format_file.extension());
+    }
+
+    let compress = args.compress || is_gzip_output;
+    if compress {
+        output_path.as_mut_os_string().push(GZIP_EXTENSION);
+    }
 
     Params {
         input_file_name: input_path,
         output_file_name: output_path,
         format: args.format_file,
-        verbose: args.verbose,
+        verbose: args.verbose || args.verbose_json,
+        verbose_json: args.verbose_json,
+        quiet: args.quiet,
+        progress: args.progress.is_enabled(args.quiet),
         nexus_url: args.nexus_url,
+        fail_on_empty: args.fail_on_empty,
+        max_age: args.flag_older_than,
+        fail_on_stale: args.fail_on_stale,
+        fail_on_error: args.fail_on_error,
+        cache_dir: args.cache_dir.map(PathBuf::from),
+        offline: args.offline,
+        prefer_cache: args.prefer_cache,
+        timestamp: args.timestamp,
+        normalize_description: args.normalize_whitespace_in_description,
+        registry_url: args
+            .registry_url
+            .unwrap_or_else(|| Url::parse(gem::DEFAULT_REGISTRY_URL).unwrap()),
+        registry_credentials: args.registry_token.map(gem::Credentials::Token),
+        deny_sources: args.deny_sources,
+        only_groups: args.only_groups,
+        exclude_groups: args.exclude_groups,
+        include_dev_dependencies: args.include_dev_dependencies,
+        client_options: ClientOptions {
+            proxy: args.proxy,
+            registry_timeout: get_timeout(
+                args.registry_timeout.or(args.timeout),
+                DEFAULT_REGISTRY_TIMEOUT_SECS,
+            ),
+            nexus_timeout: get_timeout(
+                args.nexus_timeout.or(args.timeout),
+                DEFAULT_NEXUS_TIMEOUT_SECS,
+            ),
+            connect_timeout: args.connect_timeout.map(Duration::from_secs),
+            retries: args.retries,
+            trace_requests: args.trace_requests,
+        },
+        dedupe_by: args.components_dedupe_by,
+        fetch_order: args.fetch_order,
+        annotations: args.annotations,
+        fail_on_missing: args.fail_on_missing,
+        group_from_source: args.component_group_from_source,
+        concurrency_warmup: Duration::from_millis(args.concurrency_warmup),
+        nexus_credentials: match (args.nexus_token, args.nexus_user) {
+            (Some(token), _) => Some(gem::Credentials::Token(token)),
+            (None, Some(user)) => Some(gem::Credentials::Basic(user, args.nexus_password)),
+            (None, None) => None,
+        },
+        nexus_format: args.nexus_format,
+        artifactory_url: args.artifactory_url,
+        artifactory_repo: args.artifactory_repo,
+        artifactory_credentials: args.artifactory_token.map(gem::Credentials::Token),
+        strict: args.strict,
+        evidence_occurrences: args.component_evidence_occurrences,
+        nexus_report: args.nexus_report,
+        nexus_report_all: args.nexus_report_all,
+        error_report: args.error_report,
+        compact_index: args.rubygems_info_endpoint,
+        target_platform: args.target_platform,
+        include_versionless: args.include_versionless,
+        component_depth: args.component_depth,
+        output_mode: args.output_mode,
+        name_filter: args.components_name_filter,
+        extra_bom_fields: args.extra_bom_fields,
+        root_type: args.root_type,
+        component_type: args.component_type,
+        recursive: args.recursive,
+        recursive_output: args.recursive_output,
+        redact_pattern: args.redact_description_matching,
+        validate: args.validate,
+        compress,
+        purl_qualifiers: args.purl_qualifiers,
+    }
+}
+
+//
+// Converts timeout in seconds to duration, 0 disables timeout
+//
+fn get_timeout(seconds: Option<u64>, default: u64) -> Option<Duration> {
+    match seconds.unwrap_or(default) {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    }
+}
+
+///
+/// Checks if input path means reading Gemfile.lock from stdin
+///
+pub(crate) fn is_stdin_path(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+//
+// Checks if output path points to bom file instead of directory
+//
+fn is_bom_file_name(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            BOM_EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
+
+//
+// Checks if output path is compressed bom file, e.g. `bom.json.gz`
+//
+fn has_gzip_extension(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"))
+}
+
+//
+// Checks if bom file extension differs from output format, e.g.
+// `bom.json` with xml format. Extension of compressed bom file is
+// checked without `.gz` suffix, `.yml` is allowed for yaml format
+//
+fn has_extension_mismatch(path: &Path, format: &Format) -> bool {
+    let path = if has_gzip_extension(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    };
+
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            let is_yml = *format == Format::Yaml && extension.eq_ignore_ascii_case("yml");
+            !is_yml && !extension.eq_ignore_ascii_case(format.extension())
+        })
+}
+
+//
+// Reads registry credentials from environment the same way bundler does
+//
+fn bundler_env_credentials(url: &Url) -> Option<gem::Credentials> {
+    let host = url.host_str()?;
+    let value = env::var(bundler_env_name(host)).ok()?;
+
+    Some(gem::Credentials::from_bundler(&value))
+}
+
+//
+// Builds bundler credentials variable name: gems.my-company.com becomes
+// BUNDLE_GEMS__MY___COMPANY__COM
+//
+fn bundler_env_name(host: &str) -> String {
+    let key = host.to_uppercase().replace('-', "___").replace('.', "__");
+
+    format!("BUNDLE_{key}")
+}
+
+//
+// Validates url argument
+//
+fn parse_url(value: &str) -> Result<Url, String> {
+    Url::parse(value).map_err(|error| format!("Incorrect url {value}: {error}"))
+}
+
+//
+// Validates regular expression argument
+//
+fn parse_regex(value: &str) -> Result<Regex, String> {
+    Regex::new(value).map_err(|error| format!("Incorrect regular expression {value}: {error}"))
+}
+
+//
+// Validates octal file mode argument
+//
+fn parse_mode(value: &str) -> Result<u32, String> {
+    u32::from_str_radix(value, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| format!("Incorrect file mode {value}, expected octal number like 644"))
+}
+
+//
+// Validates extra bom field argument: key should not be empty and should
+// not clash with standard bom fields
+//
+fn parse_extra_field(value: &str) -> Result<(String, String), String> {
+    let (key, field_value) = value
+        .split_once('=')
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("Incorrect bom field {value}, expected KEY=VALUE"))?;
+
+    if bom_se::STANDARD_FIELDS.contains(&key) {
+        return Err(format!("Bom field {key} is a standard field"));
     }
+
+    Ok((key.to_string(), field_value.to_string()))
 }
 
 fn print_params(params: &Params) {
-    println!("Output file format: {}", params.format);
-    println!(
+    log::debug!(target: "params", "Output file format: {}", params.format);
+    log::debug!(
+        target: "params",
         "Input directory (should contains Gemfile.lock file): {}",
-        params.input_file_name.to_str().unwrap()
+        params.input_file_name.display()
     );
-    println!(
+    log::debug!(
+        target: "params",
         "Output directory (will place bom file): {}",
-        params.output_file_name.to_str().unwrap()
+        params.output_file_name.display()
     );
 }
 
+impl Format {
+    ///
+    /// Returns extension of bom file in this format
+    ///
+    pub(crate) fn extension(&self) -> &'static str {
+        match *self {
+            Format::Protobuf => "bin",
+            Format::Json => "json",
+            Format::Xml => "xml",
+            Format::Yaml => "yaml",
+        }
+    }
+}
+
 impl fmt::Display for Format {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let str = match *self {
             Format::Json => "json",
             Format::Xml => "xml",
+            Format::Yaml => "yaml",
+            Format::Protobuf => "protobuf",
         };
 
         write!(f, "{}", str)
@@ -117,8 +746,13 @@ impl fmt::Display for Format {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_params, Args, Format};
+    use super::{
+        bundler_env_name, has_extension_mismatch, is_stdin_path, parse_mode, parse_params,
+        parse_test_params, Args, Format,
+    };
+    use clap::Parser;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     #[test]
     fn test_when_all_paths_none() {
@@ -129,6 +763,7 @@ mod tests {
             format_file: Format::Json,
             verbose: false,
             nexus_url: None,
+            ..Default::default()
         };
 
         let result = parse_params(args, cwd);
@@ -155,6 +790,7 @@ mod tests {
             format_file: Format::Xml,
             verbose: true,
             nexus_url: None,
+            ..Default::default()
         };
 
         let result = parse_params(args, cwd);
@@ -172,6 +808,71 @@ mod tests {
         assert_eq!(result.nexus_url, None);
     }
 
+    #[test]
+    fn test_when_input_is_stdin() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args {
+            path: Some(String::from("-")),
+            ..Default::default()
+        };
+
+        let result = parse_params(args, cwd);
+
+        assert!(is_stdin_path(&result.input_file_name));
+        assert_eq!(
+            result.output_file_name.to_str().unwrap(),
+            "/Users/me/work/rust/cyclonedx-rs-gem/bom.json"
+        );
+    }
+
+    #[test]
+    fn test_when_input_is_archive() {
+        let archive = parse_test_params(&["--path", "/srv/releases/app.tar.gz"]);
+        let sub_path = parse_test_params(&["--path", "/srv/releases/app.tar.gz/billing"]);
+
+        assert_eq!(
+            archive.input_file_name.to_str().unwrap(),
+            "/srv/releases/app.tar.gz/Gemfile.lock"
+        );
+        assert_eq!(
+            archive.output_file_name.to_str().unwrap(),
+            "/srv/releases/bom.json"
+        );
+        assert_eq!(
+            sub_path.input_file_name.to_str().unwrap(),
+            "/srv/releases/app.tar.gz/billing/Gemfile.lock"
+        );
+        assert_eq!(
+            sub_path.output_file_name.to_str().unwrap(),
+            "/srv/releases/bom.json"
+        );
+    }
+
+    #[test]
+    fn test_compressed_output() {
+        let default = parse_test_params(&["--compress"]);
+        let by_name = parse_test_params(&["-o", "/tmp/bom.xml.gz", "-f", "xml"]);
+        let directory = parse_test_params(&["-o", "/tmp/boms", "--compress"]);
+        let plain = parse_test_params(&[]);
+
+        assert!(default.compress);
+        assert_eq!(
+            default.output_file_name,
+            PathBuf::from("/tmp/project/bom.json.gz")
+        );
+        assert!(by_name.compress);
+        assert_eq!(by_name.output_file_name, PathBuf::from("/tmp/bom.xml.gz"));
+        assert!(!has_extension_mismatch(
+            &by_name.output_file_name,
+            &Format::Xml
+        ));
+        assert_eq!(
+            directory.output_file_name,
+            PathBuf::from("/tmp/boms/bom.json.gz")
+        );
+        assert!(!plain.compress);
+    }
+
     #[test]
     fn test_when_output_exists_intup_none() {
         let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
@@ -181,6 +882,7 @@ mod tests {
             format_file: Format::Xml,
             verbose: true,
             nexus_url: None,
+            ..Default::default()
         };
 
         let result = parse_params(args, cwd);
@@ -207,6 +909,7 @@ mod tests {
             format_file: Format::Json,
             verbose: false,
             nexus_url: Some(String::from("https://somenexus.com/")),
+            ..Default::default()
         };
 
         let result = parse_params(args, cwd);
@@ -222,5 +925,510 @@ mod tests {
             "/Users/me/work/ruby/railsapp/bom.json"
         );
         assert_eq!(result.nexus_url.unwrap().as_str(), "https://somenexus.com/");
+        assert!(!result.fail_on_empty);
+    }
+
+    #[test]
+    fn test_when_verbose_json_set() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args {
+            verbose_json: true,
+            ..Default::default()
+        };
+
+        let result = parse_params(args, cwd);
+
+        assert!(result.verbose);
+        assert!(result.verbose_json);
+    }
+
+    #[test]
+    fn test_when_fail_on_empty_set() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args {
+            fail_on_empty: true,
+            ..Default::default()
+        };
+
+        let result = parse_params(args, cwd);
+
+        assert!(result.fail_on_empty);
+    }
+
+    #[test]
+    fn test_when_flag_older_than_set() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--flag-older-than",
+            "2y",
+            "--fail-on-stale",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert_eq!(result.max_age, Some(730));
+        assert!(result.fail_on_stale);
+    }
+
+    #[test]
+    fn test_when_offline_set() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--cache-dir",
+            "/var/cache/gems",
+            "--offline",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert_eq!(result.cache_dir, Some(PathBuf::from("/var/cache/gems")));
+        assert!(result.offline);
+    }
+
+    #[test]
+    fn test_when_offline_without_cache_dir() {
+        let result = Args::try_parse_from(["cyclonedx-rs-gem", "--offline"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_when_registry_url_set() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--registry-url",
+            "https://gems.mycompany.com/",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert_eq!(result.registry_url.as_str(), "https://gems.mycompany.com/");
+    }
+
+    #[test]
+    fn test_when_registry_url_default() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from(["cyclonedx-rs-gem"]);
+
+        let result = parse_params(args, cwd);
+
+        assert_eq!(result.registry_url.as_str(), "https://rubygems.org/");
+    }
+
+    #[test]
+    fn test_when_registry_url_malformed() {
+        let result = Args::try_parse_from(["cyclonedx-rs-gem", "--registry-url", "not a url"]);
+
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Incorrect url not a url"));
+    }
+
+    #[test]
+    fn test_when_registry_token_set() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--registry-url",
+            "https://gems.mycompany.com/",
+            "--registry-token",
+            "secret",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert!(result.registry_credentials.is_some());
+    }
+
+    #[test]
+    fn test_bundler_env_name() {
+        assert_eq!(
+            bundler_env_name("gems.my-company.com"),
+            "BUNDLE_GEMS__MY___COMPANY__COM"
+        );
+    }
+
+    #[test]
+    fn test_when_deny_source_repeated() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--deny-source",
+            "mirror.example.com",
+            "--deny-source",
+            "gems.example.org",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert_eq!(
+            result.deny_sources,
+            vec!["mirror.example.com", "gems.example.org"]
+        );
+    }
+
+    #[test]
+    fn test_when_proxy_set() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--proxy",
+            "http://proxy.mycompany.com:3128",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert_eq!(
+            result.client_options.proxy.unwrap().as_str(),
+            "http://proxy.mycompany.com:3128/"
+        );
+    }
+
+    #[test]
+    fn test_when_proxy_malformed() {
+        let result = Args::try_parse_from(["cyclonedx-rs-gem", "--proxy", "not a url"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_timeout() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+
+        let default = parse_params(Args::parse_from(["cyclonedx-rs-gem"]), cwd.clone());
+        let custom = parse_params(
+            Args::parse_from(["cyclonedx-rs-gem", "--timeout", "5"]),
+            cwd.clone(),
+        );
+        let disabled = parse_params(
+            Args::parse_from(["cyclonedx-rs-gem", "--timeout", "0"]),
+            cwd,
+        );
+
+        assert_eq!(
+            default.client_options.registry_timeout,
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            default.client_options.nexus_timeout,
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            custom.client_options.registry_timeout,
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            custom.client_options.nexus_timeout,
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(disabled.client_options.registry_timeout, None);
+        assert_eq!(disabled.client_options.nexus_timeout, None);
+    }
+
+    #[test]
+    fn test_registry_and_nexus_timeouts() {
+        let registry = parse_test_params(&["--registry-timeout", "10"]);
+        let nexus = parse_test_params(&["--nexus-timeout", "120"]);
+        let both = parse_test_params(&[
+            "--timeout",
+            "5",
+            "--registry-timeout",
+            "0",
+            "--nexus-timeout",
+            "90",
+        ]);
+
+        assert_eq!(
+            registry.client_options.registry_timeout,
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            registry.client_options.nexus_timeout,
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            nexus.client_options.registry_timeout,
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            nexus.client_options.nexus_timeout,
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(both.client_options.registry_timeout, None);
+        assert_eq!(
+            both.client_options.nexus_timeout,
+            Some(Duration::from_secs(90))
+        );
+    }
+
+    #[test]
+    fn test_connect_timeout() {
+        let default = parse_test_params(&[]);
+        let custom = parse_test_params(&["--connect-timeout", "3"]);
+
+        assert_eq!(default.client_options.connect_timeout, None);
+        assert_eq!(
+            custom.client_options.connect_timeout,
+            Some(Duration::from_secs(3))
+        );
+    }
+
+    #[test]
+    fn test_retries() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+
+        let default = parse_params(Args::parse_from(["cyclonedx-rs-gem"]), cwd.clone());
+        let custom = parse_params(
+            Args::parse_from(["cyclonedx-rs-gem", "--retries", "0"]),
+            cwd,
+        );
+
+        assert_eq!(default.client_options.retries, None);
+        assert_eq!(custom.client_options.retries, Some(0));
+    }
+
+    #[test]
+    fn test_output_file_extension_mismatch() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--format-file",
+            "xml",
+            "--output",
+            "/tmp/sbom/bom.json",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert_eq!(
+            result.output_file_name.to_str().unwrap(),
+            "/tmp/sbom/bom.json"
+        );
+        assert!(has_extension_mismatch(
+            &result.output_file_name,
+            &result.format
+        ));
+    }
+
+    #[test]
+    fn test_output_file_fix_extension() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--format-file",
+            "xml",
+            "--output",
+            "/tmp/sbom/bom.json",
+            "--fix-extension",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert_eq!(
+            result.output_file_name.to_str().unwrap(),
+            "/tmp/sbom/bom.xml"
+        );
+        assert!(!has_extension_mismatch(
+            &result.output_file_name,
+            &result.format
+        ));
+    }
+
+    #[test]
+    fn test_protobuf_format() {
+        let by_dir = parse_test_params(&["-f", "protobuf", "-o", "/tmp/sbom"]);
+        let by_name = parse_test_params(&["-f", "yaml", "-o", "/tmp/sbom/sbom.yml"]);
+
+        assert_eq!(by_dir.output_file_name, PathBuf::from("/tmp/sbom/bom.bin"));
+        assert_eq!(
+            by_name.output_file_name,
+            PathBuf::from("/tmp/sbom/sbom.yml")
+        );
+    }
+
+    #[test]
+    fn test_yaml_format() {
+        let result = parse_test_params(&["-f", "yaml", "-o", "/tmp/sbom"]);
+
+        assert_eq!(result.output_file_name, PathBuf::from("/tmp/sbom/bom.yaml"));
+        assert!(!has_extension_mismatch(
+            &PathBuf::from("/tmp/sbom/bom.yml"),
+            &Format::Yaml
+        ));
+        assert!(has_extension_mismatch(
+            &PathBuf::from("/tmp/sbom/bom.yml"),
+            &Format::Json
+        ));
+    }
+
+    #[test]
+    fn test_nexus_credentials_not_printed() {
+        let cwd = PathBuf::from("/Users/me/work/rust/cyclonedx-rs-gem/");
+        let args = Args::parse_from([
+            "cyclonedx-rs-gem",
+            "--nexus-url",
+            "https://mynexus.com",
+            "--nexus-user",
+            "ci",
+            "--nexus-password",
+            "secret",
+        ]);
+
+        let result = parse_params(args, cwd);
+
+        assert!(result.nexus_credentials.is_some());
+        assert!(!format!("{:?}", result).contains("secret"));
+    }
+
+    #[test]
+    fn test_nexus_token_conflicts_with_user() {
+        let result = Args::try_parse_from([
+            "cyclonedx-rs-gem",
+            "--nexus-url",
+            "https://mynexus.com",
+            "--nexus-user",
+            "ci",
+            "--nexus-token",
+            "secret",
+        ]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("644"), Ok(0o644));
+        assert_eq!(parse_mode("0600"), Ok(0o600));
+        assert!(parse_mode("888").is_err());
+        assert!(parse_mode("17777").is_err());
+    }
+
+    #[test]
+    fn test_quiet_conflicts_with_verbose() {
+        let quiet = Args::try_parse_from(["cyclonedx-rs-gem", "--quiet"]);
+        let both = Args::try_parse_from(["cyclonedx-rs-gem", "--quiet", "--verbose"]);
+
+        assert!(quiet.is_ok_and(|args| args.quiet));
+        assert!(both.is_err());
+    }
+
+    #[test]
+    fn test_invalid_name_filter() {
+        let valid = Args::try_parse_from(["cyclonedx-rs-gem", "--name-filter", "^active"]);
+        let invalid = Args::try_parse_from(["cyclonedx-rs-gem", "--components-name-filter", "("]);
+
+        assert!(valid.is_ok_and(|args| args.components_name_filter.is_some()));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_root_type() {
+        let default = parse_test_params(&[]);
+        let library = parse_test_params(&["--root-type", "library"]);
+
+        assert_eq!(default.root_type, None);
+        assert_eq!(
+            library.root_type,
+            Some(crate::bom_se::ComponentType::Library)
+        );
+    }
+
+    #[test]
+    fn test_artifactory() {
+        let params = parse_test_params(&[
+            "--artifactory-url",
+            "https://mycompany.jfrog.io/artifactory",
+            "--artifactory-repo",
+            "gems-local",
+            "--artifactory-token",
+            "secret",
+            "--fail-on-missing",
+        ]);
+        let both = Args::try_parse_from([
+            "cyclonedx-rs-gem",
+            "--nexus-url",
+            "https://mynexus.com",
+            "--artifactory-url",
+            "https://mycompany.jfrog.io/artifactory",
+        ]);
+        let without_repository = Args::try_parse_from(["cyclonedx-rs-gem", "--fail-on-missing"]);
+
+        assert_eq!(
+            params.artifactory_url.as_deref(),
+            Some("https://mycompany.jfrog.io/artifactory")
+        );
+        assert_eq!(params.artifactory_repo.as_deref(), Some("gems-local"));
+        assert!(params.artifactory_credentials.is_some());
+        assert!(params.fail_on_missing);
+        assert!(both.is_err());
+        assert!(without_repository.is_err());
+    }
+
+    #[test]
+    fn test_target_platform() {
+        let default = parse_test_params(&[]);
+        let linux = parse_test_params(&["--target-platform", "x86_64-linux"]);
+
+        assert_eq!(default.target_platform, None);
+        assert_eq!(linux.target_platform.as_deref(), Some("x86_64-linux"));
+    }
+
+    #[test]
+    fn test_progress() {
+        let default = Args::try_parse_from(["cyclonedx-rs-gem"]).unwrap();
+        let always = parse_test_params(&["--progress", "always"]);
+        let never = parse_test_params(&["--progress", "never"]);
+        let quiet = parse_test_params(&["--quiet"]);
+        let invalid = Args::try_parse_from(["cyclonedx-rs-gem", "--progress", "sometimes"]);
+
+        assert_eq!(default.progress, crate::progress::ProgressMode::Auto);
+        assert!(always.progress);
+        assert!(!never.progress);
+        assert!(!quiet.progress);
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_component_type() {
+        let default = parse_test_params(&[]);
+        let framework = parse_test_params(&["--component-type", "framework"]);
+        let invalid = Args::try_parse_from(["cyclonedx-rs-gem", "--component-type", "device"]);
+
+        assert_eq!(
+            default.component_type,
+            crate::bom_se::ComponentType::Library
+        );
+        assert_eq!(
+            framework.component_type,
+            crate::bom_se::ComponentType::Framework
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_extra_bom_fields() {
+        let params = parse_test_params(&[
+            "--extra-bom-field",
+            "tenant=payments",
+            "--extra-bom-field",
+            "env=prod=eu",
+        ]);
+        let standard = Args::try_parse_from(["cyclonedx-rs-gem", "--extra-bom-field", "version=2"]);
+        let malformed = Args::try_parse_from(["cyclonedx-rs-gem", "--extra-bom-field", "tenant"]);
+
+        assert_eq!(
+            params.extra_bom_fields,
+            vec![
+                (String::from("tenant"), String::from("payments")),
+                (String::from("env"), String::from("prod=eu")),
+            ]
+        );
+        assert!(standard.is_err());
+        assert!(malformed.is_err());
     }
 }
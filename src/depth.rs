@@ -0,0 +1,149 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::gem::{Gemspec, Property, Scope};
+
+pub(crate) const DEPTH_PROPERTY: &str = "cdx:bundler:depth";
+const UNREACHABLE_DEPTH: &str = "unreachable";
+
+///
+/// Computes minimum depth of every gem in dependency tree. Gems declared
+/// in Gemfile have depth 1, their dependencies 2 and so on. Gems, which
+/// could not be reached from declared ones, are absent in result
+///
+pub(crate) fn compute_depths(gems: &[Gemspec], direct: &[String]) -> HashMap<String, usize> {
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for gem in gems {
+        edges
+            .entry(&gem.name)
+            .or_default()
+            .extend(gem.dependencies.iter().map(String::as_str));
+    }
+
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    let mut queue: VecDeque<(&str, usize)> = VecDeque::new();
+    for name in direct {
+        if !depths.contains_key(name) {
+            depths.insert(name.clone(), 1);
+            queue.push_back((name, 1));
+        }
+    }
+
+    while let Some((name, depth)) = queue.pop_front() {
+        for dependency in edges.get(name).into_iter().flatten() {
+            if !depths.contains_key(*dependency) {
+                depths.insert(dependency.to_string(), depth + 1);
+                queue.push_back((dependency, depth + 1));
+            }
+        }
+    }
+
+    depths
+}
+
+///
+/// Adds `cdx:bundler:depth` property to every gem. Gems unreachable from
+/// Gemfile declared ones are marked as `unreachable`
+///
+pub(crate) fn mark_depths(gems: &mut [Gemspec], direct: &[String]) {
+    let depths = compute_depths(gems, direct);
+
+    for gem in gems.iter_mut() {
+        let depth = depths
+            .get(&gem.name)
+            .map_or_else(|| String::from(UNREACHABLE_DEPTH), ToString::to_string);
+
+        gem.properties.push(Property::new(DEPTH_PROPERTY, depth));
+    }
+}
+
+///
+/// Sets scope of every gem: gems declared in Gemfile are required,
+/// their dependencies are optional
+///
+pub(crate) fn mark_scopes(gems: &mut [Gemspec], direct: &[String]) {
+    for gem in gems.iter_mut() {
+        gem.scope = Some(match direct.contains(&gem.name) {
+            true => Scope::Required,
+            false => Scope::Optional,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_gem(name: &str, dependencies: &[&str]) -> Gemspec {
+        Gemspec {
+            name: name.to_string(),
+            dependencies: dependencies.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_depths() {
+        let gems = vec![
+            build_gem("rails", &["actionpack", "activesupport"]),
+            build_gem("actionpack", &["rack", "activesupport"]),
+            build_gem("activesupport", &["concurrent-ruby"]),
+            build_gem("rack", &[]),
+            build_gem("concurrent-ruby", &[]),
+            build_gem("rake", &[]),
+            build_gem("orphan", &["rack"]),
+        ];
+        let direct = vec![String::from("rails"), String::from("rake")];
+
+        let result = compute_depths(&gems, &direct);
+
+        assert_eq!(result["rails"], 1);
+        assert_eq!(result["rake"], 1);
+        assert_eq!(result["actionpack"], 2);
+        assert_eq!(result["activesupport"], 2);
+        assert_eq!(result["rack"], 3);
+        assert_eq!(result["concurrent-ruby"], 3);
+        assert!(!result.contains_key("orphan"));
+    }
+
+    #[test]
+    fn test_mark_depths() {
+        let mut gems = vec![
+            build_gem("rails", &["rack"]),
+            build_gem("rack", &[]),
+            build_gem("orphan", &[]),
+        ];
+
+        mark_depths(&mut gems, &[String::from("rails")]);
+
+        let depths: Vec<&str> = gems
+            .iter()
+            .map(|gem| gem.properties[0].value.as_str())
+            .collect();
+        assert_eq!(depths, vec!["1", "2", "unreachable"]);
+        assert_eq!(gems[0].properties[0].name, "cdx:bundler:depth");
+    }
+
+    #[test]
+    fn test_mark_scopes() {
+        let gemfile = r#"GEM
+  remote: https://rubygems.org/
+  specs:
+    rails (7.1.1)
+      rack
+    rack (3.0.8)
+
+DEPENDENCIES
+  rails (~> 7.1)
+
+BUNDLED WITH
+   2.5.3
+"#;
+        let specs = crate::bundler::parse_gemfile(String::from(gemfile));
+        let mut gems = vec![build_gem("rails", &["rack"]), build_gem("rack", &[])];
+
+        mark_scopes(&mut gems, &specs.direct);
+
+        assert_eq!(gems[0].scope, Some(Scope::Required));
+        assert_eq!(gems[1].scope, Some(Scope::Optional));
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use crate::errors::FetchPackageError;
+
+///
+/// Gem, which information could not be fetched from gems registry
+///
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct FetchFailure {
+    name: String,
+    version: Option<String>,
+    kind: &'static str,
+    message: String,
+}
+
+impl FetchFailure {
+    ///
+    /// Builds failure for Gemfile.lock item from fetch error
+    ///
+    pub(crate) fn new(name: &str, version: Option<&str>, error: &FetchPackageError) -> Self {
+        FetchFailure {
+            name: name.to_string(),
+            version: version.map(str::to_string),
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
+
+    ///
+    /// Formats failed gem as `name version` for logs and summaries
+    ///
+    pub(crate) fn label(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} {version}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+///
+/// Serializes fetch failures to json report. Empty list is serialized
+/// too, so report always reflects the last run
+///
+pub(crate) fn build_report(failures: &[FetchFailure]) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_report() {
+        let failures = vec![
+            FetchFailure::new(
+                "rails",
+                Some("7.1.1"),
+                &FetchPackageError::PackageNotFound(String::from("rails"), String::from("7.1.1")),
+            ),
+            FetchFailure::new(
+                "rake",
+                None,
+                &FetchPackageError::Timeout(String::from("rake"), String::from("latest")),
+            ),
+        ];
+
+        let report = build_report(&failures).unwrap();
+        let expected = r#"[
+  {
+    "name": "rails",
+    "version": "7.1.1",
+    "kind": "package_not_found",
+    "message": "Gem not found: rails, version 7.1.1"
+  },
+  {
+    "name": "rake",
+    "version": null,
+    "kind": "timeout",
+    "message": "Request timed out for gem rake version latest"
+  }
+]"#;
+
+        assert_eq!(report, expected);
+    }
+
+    #[test]
+    fn test_build_empty_report() {
+        assert_eq!(build_report(&[]).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_label() {
+        let error =
+            FetchPackageError::ServerError(String::from("rake"), String::from("13.0.6"), 500);
+
+        assert_eq!(
+            FetchFailure::new("rake", Some("13.0.6"), &error).label(),
+            "rake 13.0.6"
+        );
+        assert_eq!(FetchFailure::new("rake", None, &error).label(), "rake");
+    }
+}
@@ -4,34 +4,102 @@ use thiserror::Error;
 /// Errors for requests to rubygems.org
 ///
 #[derive(Error, Debug)]
-pub(crate) enum FetchPackageError {
+pub enum FetchPackageError {
     #[error("Could not send request for gem {0} version {1}")]
     SendRequestError(String, String),
     #[error("Could not parse response for gem {0} version {1}")]
     ParseResponseError(String, String),
-    #[error("Could not find version {0} for gem {1}")]
+    #[error("Could not find version {1} for gem {0}")]
     VersionNotFound(String, String),
     #[error("Gem not found: {0}, version {1}")]
     PackageNotFound(String, String),
-    #[error("Client error occurred for gem {0}, version {1}")]
-    ClientError(String, String),
-    #[error("Server error occurred for gem {0}, version {1}")]
-    ServerError(String, String),
-    #[error("Unknown error occurred for gem {0}, version {1}")]
-    UnknownError(String, String),
+    #[error("Rate limited by registry for gem {0}, version {1}")]
+    RateLimited(String, String),
+    #[error("Client error {2} occurred for gem {0}, version {1}")]
+    ClientError(String, String, u16),
+    #[error("Server error {2} occurred for gem {0}, version {1}")]
+    ServerError(String, String, u16),
+    #[error("Unknown error {2} occurred for gem {0}, version {1}")]
+    UnknownError(String, String, u16),
+    #[error("Request timed out for gem {0} version {1}")]
+    Timeout(String, String),
+    #[error("Gem {0} version {1} not found in cache")]
+    CacheMiss(String, String),
+    #[error("Truncated response for gem {0} version {1}")]
+    TruncatedResponse(String, String),
+    #[error("Gem {0} version {1} is yanked")]
+    VersionYanked(String, String),
+}
+
+impl FetchPackageError {
+    ///
+    /// Returns machine readable kind of error for reports
+    ///
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FetchPackageError::SendRequestError(_, _) => "send_request_error",
+            FetchPackageError::ParseResponseError(_, _) => "parse_response_error",
+            FetchPackageError::VersionNotFound(_, _) => "version_not_found",
+            FetchPackageError::PackageNotFound(_, _) => "package_not_found",
+            FetchPackageError::RateLimited(_, _) => "rate_limited",
+            FetchPackageError::ClientError(_, _, _) => "client_error",
+            FetchPackageError::ServerError(_, _, _) => "server_error",
+            FetchPackageError::UnknownError(_, _, _) => "unknown_error",
+            FetchPackageError::Timeout(_, _) => "timeout",
+            FetchPackageError::CacheMiss(_, _) => "cache_miss",
+            FetchPackageError::TruncatedResponse(_, _) => "truncated_response",
+            FetchPackageError::VersionYanked(_, _) => "version_yanked",
+        }
+    }
+}
+
+///
+/// Errors for requests to packages repository (Nexus or Artifactory),
+/// first field is repository kind
+///
+#[derive(Error, Debug)]
+pub enum RepoError {
+    #[error("Incorrect {0} url: {1}")]
+    UrlParse(&'static str, String),
+    #[error("Failed to build http client for {0}")]
+    BuildClient(&'static str),
+    #[error("Could not send request to {0} for gem {1} version {2}")]
+    SendRequest(&'static str, String, String),
+    #[error("Could not parse {0} response for gem {1} version {2}")]
+    ParseResponse(&'static str, String, String),
 }
 
 ///
 /// Errors for requests to nexus repository
 ///
+pub type NexusError = RepoError;
+
+///
+/// Errors of command line run, which are reported with specific process
+/// exit code
+///
+#[derive(Error, Debug)]
+pub enum RunError {
+    #[error("{0}")]
+    EmptyBom(String),
+}
+
+impl RunError {
+    ///
+    /// Returns process exit code for error
+    ///
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunError::EmptyBom(_) => 2,
+        }
+    }
+}
+
+///
+/// Errors for generated package urls
+///
 #[derive(Error, Debug)]
-pub(crate) enum NexusError {
-    #[error("Incorrect Nexus url: {0}")]
-    UrlParse(String),
-    #[error("Failed to build http client for Nexus")]
-    BuildClient,
-    #[error("Could not send request to Nexus for gem {0} version {1}")]
-    SendRequest(String, String),
-    #[error("Could not parse Nexus response for gem {0} version {1}")]
-    ParseResponse(String, String),
+pub enum PurlError {
+    #[error("Malformed purl {0}: {1}")]
+    Malformed(String, &'static str),
 }
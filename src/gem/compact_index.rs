@@ -0,0 +1,79 @@
+use super::{GemspecResponse, RUBY_PLATFORM};
+
+const HEADER: &str = "---";
+const CHECKSUM_REQUIREMENT: &str = "checksum:";
+
+///
+/// Parses compact index `/info/<gem>` response. Every line after `---`
+/// header describes one gem version:
+/// `<version>[-<platform>] <dependencies>|<requirements>`, where
+/// requirements contain `checksum:<sha256>`. Compact index has no
+/// summary, authors or licenses, so they are left empty
+///
+pub(super) fn parse(body: &str) -> Vec<GemspecResponse> {
+    body.lines()
+        .skip_while(|line| line.trim() != HEADER)
+        .skip(1)
+        .filter_map(parse_line)
+        .collect()
+}
+
+//
+// Parses one version line, malformed lines are skipped
+//
+fn parse_line(line: &str) -> Option<GemspecResponse> {
+    let (release, rest) = line.split_once(' ')?;
+    let (_, requirements) = rest.split_once('|')?;
+
+    // platform follows the first dash, versions never contain dashes
+    let (number, platform) = release.split_once('-').unwrap_or((release, RUBY_PLATFORM));
+    let sha = requirements
+        .split(',')
+        .find_map(|requirement| requirement.strip_prefix(CHECKSUM_REQUIREMENT))?;
+
+    Some(GemspecResponse {
+        number: number.to_string(),
+        platform: platform.to_string(),
+        sha: sha.to_string(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compact_index() {
+        let body = "---
+1.16.4 mini_portile2:~> 2.8.2,racc:~> 1.4|checksum:9dd5d31d3b6a0de5b7d0a1b8ed9a2bbbd8f3e2b5fa2a4f3b4fbb5f2a0d7d3b41,ruby:>= 3.0,rubygems:>= 3.3.22
+1.16.5 mini_portile2:~> 2.8.2,racc:~> 1.4|checksum:ffdcb1cbbb9a5d6f0c4a5c5a6d28a7b16a0c2e9e4c3c1e6d2a5f4b3e2d1c0b9a8,ruby:>= 3.0
+1.16.5-x86_64-linux racc:~> 1.4|checksum:0ca238da870066bed2f7837af6f35791bb9b76c4c5638999c46aac44818a6a97,ruby:< 3.4.dev&>= 3.0
+broken line
+";
+
+        let result = parse(body);
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[1].number, "1.16.5");
+        assert_eq!(result[1].platform, "ruby");
+        assert_eq!(
+            result[1].sha,
+            "ffdcb1cbbb9a5d6f0c4a5c5a6d28a7b16a0c2e9e4c3c1e6d2a5f4b3e2d1c0b9a8"
+        );
+        assert_eq!(result[2].platform, "x86_64-linux");
+        assert_eq!(
+            result[2].sha,
+            "0ca238da870066bed2f7837af6f35791bb9b76c4c5638999c46aac44818a6a97"
+        );
+    }
+
+    #[test]
+    fn test_parse_version_without_dependencies() {
+        let result = parse("created_at: 2024-01-01\n---\n1.0.0 |checksum:abc\n");
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].number, "1.0.0");
+        assert_eq!(result[0].sha, "abc");
+    }
+}
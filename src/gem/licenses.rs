@@ -1,25 +1,52 @@
-use serde::Serialize;
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, PartialEq)]
 pub(crate) struct KnownLicense {
     pub(crate) id: String,
     properties: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, PartialEq)]
 pub(crate) struct UnknownLicense {
     pub(crate) name: String,
     properties: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
-#[serde(untagged)]
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct LicenseExpression {
+    pub(crate) expression: String,
+}
+
+#[derive(Debug, PartialEq)]
 pub(crate) enum License {
     KnownLicense(KnownLicense),
     UnknownLicense(UnknownLicense),
+    Expression(LicenseExpression),
 }
 
-const LICENSES: &[&str] = &[
+///
+/// License choice of component. Licenses are wrapped with `license` field
+/// and expression is kept in `expression` field, as CycloneDX requires
+///
+impl Serialize for License {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self {
+            License::KnownLicense(license) => map.serialize_entry("license", license)?,
+            License::UnknownLicense(license) => map.serialize_entry("license", license)?,
+            License::Expression(license) => {
+                map.serialize_entry("expression", &license.expression)?
+            }
+        }
+
+        map.end()
+    }
+}
+
+const EXPRESSION_OPERATORS: &[&str] = &["AND", "OR", "WITH"];
+
+pub(crate) const LICENSES: &[&str] = &[
     "0BSD",
     "AAL",
     "ADSL",
@@ -455,34 +482,110 @@ const LICENSES: &[&str] = &[
 ];
 
 ///
-/// Get license information from rubygems response. It try to find license
-/// identifier in known licenses list. If license found, it return tuple
-/// like this: (Some(String::from("MIT")), None), otherwise it returns
-/// (None, Some(String::from("Unknown")))
+/// Get licenses information from rubygems response, one entry per declared
+/// license. Each license is looked up in known licenses list independently.
+/// Compound SPDX expressions like `MIT OR Apache-2.0` are returned as
+/// expression, if all its license identifiers are known. Otherwise license
+/// is returned by name. Missing, empty and blank licenses produce no entries.
+/// CycloneDX does not allow to combine expression with other licenses, so
+/// if any license is an expression, all licenses are joined with `AND` into
+/// single expression
 ///
 /// # Examples
 ///
-/// ```
-/// let licenses = vec!(String::from("MIT"), String::from("FLTK-exception"));
-/// let result = get_license(licenses);
+/// ```ignore
+/// let licenses = vec!(String::from("MIT"), String::from("Nonstandard"));
+/// let result = get_licenses(Some(licenses));
 ///
-/// assert_eq!(result, (Some(String::from("MIT")), None));
+/// assert_eq!(result, vec![
+///     License::KnownLicense(KnownLicense::new(String::from("MIT"))),
+///     License::UnknownLicense(UnknownLicense::new(String::from("Nonstandard"))),
+/// ]);
 /// ```
 ///
-pub(crate) fn get_license(licenses: Option<Vec<String>>) -> (Option<String>, Option<String>) {
-    match licenses {
-        Some(list) if !list.is_empty() => {
-            let license = list.first().unwrap();
+pub(crate) fn get_licenses(licenses: Option<Vec<String>>) -> Vec<License> {
+    let licenses = licenses
+        .unwrap_or_default()
+        .into_iter()
+        .map(|license| license.trim().to_string())
+        .filter(|license| !license.is_empty())
+        .map(get_license)
+        .collect::<Vec<License>>();
 
-            if LICENSES.contains(&license.as_str()) {
-                return (Some(String::from(license)), None);
-            }
+    if licenses.len() > 1
+        && licenses
+            .iter()
+            .any(|license| matches!(license, License::Expression(_)))
+    {
+        return vec![join_licenses(licenses)];
+    }
 
-            (None, Some(String::from(license)))
-        }
-        Some(_list) => (None, None),
-        None => (None, None),
+    licenses
+}
+
+//
+// Joins licenses with AND into single expression. Compound expressions
+// are wrapped with parentheses to keep their operators precedence
+//
+fn join_licenses(licenses: Vec<License>) -> License {
+    let expression = licenses
+        .into_iter()
+        .map(|license| match license {
+            License::KnownLicense(license) => license.id,
+            License::UnknownLicense(license) => license.name,
+            License::Expression(license) => format!("({})", license.expression),
+        })
+        .collect::<Vec<String>>()
+        .join(" AND ");
+
+    License::Expression(LicenseExpression::new(expression))
+}
+
+//
+// Classifies single license as known SPDX identifier, SPDX expression
+// or unknown license name
+//
+fn get_license(license: String) -> License {
+    if LICENSES.contains(&license.as_str()) {
+        return License::KnownLicense(KnownLicense::new(license));
+    }
+
+    if is_spdx_expression(&license) {
+        return License::Expression(LicenseExpression::new(license));
     }
+
+    License::UnknownLicense(UnknownLicense::new(license))
+}
+
+//
+// Checks if license is compound SPDX expression, which consists of known
+// license identifiers joined by AND, OR and WITH operators. Identifiers
+// after WITH are license exceptions and are not validated
+//
+fn is_spdx_expression(license: &str) -> bool {
+    let spaced = license.replace('(', " ( ").replace(')', " ) ");
+    let tokens: Vec<&str> = spaced
+        .split_whitespace()
+        .filter(|token| *token != "(" && *token != ")")
+        .collect();
+
+    let is_operator = |token: &str| {
+        EXPRESSION_OPERATORS
+            .iter()
+            .any(|operator| operator.eq_ignore_ascii_case(token))
+    };
+
+    if !tokens.iter().any(|token| is_operator(token)) {
+        return false;
+    }
+
+    tokens.iter().enumerate().all(|(index, token)| {
+        let after_with = index > 0 && tokens[index - 1].eq_ignore_ascii_case("WITH");
+
+        is_operator(token)
+            || after_with
+            || LICENSES.contains(&token.strip_suffix('+').unwrap_or(token))
+    })
 }
 
 impl KnownLicense {
@@ -494,6 +597,12 @@ impl KnownLicense {
     }
 }
 
+impl LicenseExpression {
+    pub(crate) fn new(expression: String) -> Self {
+        LicenseExpression { expression }
+    }
+}
+
 impl UnknownLicense {
     pub(crate) fn new(name: String) -> Self {
         UnknownLicense {
@@ -509,30 +618,105 @@ mod tests {
 
     #[test]
     fn test_licenses_none() {
-        let result = get_license(None);
+        let result = get_licenses(None);
 
-        assert_eq!(result, (None, None));
+        assert!(result.is_empty());
     }
 
     #[test]
     fn test_licenses_empty_vec() {
-        let result = get_license(Some(vec![]));
+        let result = get_licenses(Some(vec![]));
+
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_licenses_blank() {
+        let result = get_licenses(Some(vec![String::new(), String::from("  ")]));
 
-        assert_eq!(result, (None, None));
+        assert!(result.is_empty());
     }
 
     #[test]
     fn test_licenses_known_license() {
-        let result = get_license(Some(vec![String::from("MIT"), String::from("MIT-0")]));
+        let result = get_licenses(Some(vec![String::from("MIT"), String::from("MIT-0")]));
 
-        assert_eq!(result, (Some(String::from("MIT")), None));
+        assert_eq!(
+            result,
+            vec![
+                License::KnownLicense(KnownLicense::new(String::from("MIT"))),
+                License::KnownLicense(KnownLicense::new(String::from("MIT-0"))),
+            ]
+        );
     }
 
     #[test]
     fn test_licenses_unknown_license() {
-        let result = get_license(Some(vec![String::from("unknown"), String::from("MIT-0")]));
+        let result = get_licenses(Some(vec![String::from("unknown"), String::from("MIT-0")]));
+
+        assert_eq!(
+            result,
+            vec![
+                License::UnknownLicense(UnknownLicense::new(String::from("unknown"))),
+                License::KnownLicense(KnownLicense::new(String::from("MIT-0"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_licenses_expression() {
+        let result = get_license(String::from("MIT OR Apache-2.0"));
+
+        assert_eq!(
+            result,
+            License::Expression(LicenseExpression::new(String::from("MIT OR Apache-2.0")))
+        );
+    }
+
+    #[test]
+    fn test_licenses_expression_with_exception() {
+        let result = get_license(String::from(
+            "(GPL-2.0-or-later WITH Classpath-exception-2.0) OR MIT",
+        ));
+
+        assert!(matches!(result, License::Expression(_)));
+    }
+
+    #[test]
+    fn test_licenses_nonstandard() {
+        let result = get_license(String::from("Nonstandard"));
+
+        assert_eq!(
+            result,
+            License::UnknownLicense(UnknownLicense::new(String::from("Nonstandard")))
+        );
+    }
+
+    #[test]
+    fn test_licenses_expression_with_unknown_id() {
+        let result = get_license(String::from("MIT OR Nonstandard"));
+
+        assert_eq!(
+            result,
+            License::UnknownLicense(UnknownLicense::new(String::from("MIT OR Nonstandard")))
+        );
+    }
+
+    #[test]
+    fn test_licenses_expression_with_other_licenses() {
+        let licenses = vec![
+            String::from("MIT"),
+            String::from("Apache-2.0 OR BSD-3-Clause"),
+            String::from("Nonstandard"),
+        ];
+        let result = get_licenses(Some(licenses));
 
-        assert_eq!(result, (None, Some(String::from("unknown"))));
+        assert_eq!(
+            result,
+            vec![License::Expression(LicenseExpression::new(String::from(
+                "MIT AND (Apache-2.0 OR BSD-3-Clause) AND Nonstandard"
+            )))]
+        );
     }
 
     #[test]
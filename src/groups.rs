@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::gem::{Gemspec, Property};
+
+pub(crate) const GROUPS_PROPERTY: &str = "cdx:bundler:groups";
+const DEFAULT_GROUP: &str = "default";
+const DEV_GROUPS: &[&str] = &["development", "test"];
+
+///
+/// Scans Gemfile content and returns bundler groups of every gem declared
+/// in it. Both `group :test do ... end` blocks and inline `group:` /
+/// `groups:` options are supported. Gems outside of any group belong to
+/// the `default` group. Only direct dependencies are listed, Gemfile
+/// does not contain transitive ones
+///
+pub(crate) fn parse_groups(gemfile_content: &str) -> HashMap<String, Vec<String>> {
+    let mut result: HashMap<String, Vec<String>> = HashMap::new();
+    // groups of every opened `do ... end` block, empty for non group blocks
+    let mut blocks: Vec<Vec<String>> = Vec::new();
+
+    let group_block_regexp = Regex::new(r"^\s*group\s+(.+?)\s+do\s*$").unwrap();
+    let other_block_regexp = Regex::new(r"\bdo\s*(\|[^|]*\|)?\s*$").unwrap();
+    let end_regexp = Regex::new(r"^\s*end\b").unwrap();
+    let gem_regexp = Regex::new(r#"^\s*gem\s+["']([^"']+)["']"#).unwrap();
+    let inline_groups_regexp =
+        Regex::new(r#"(?::groups?\s*=>|\bgroups?:)\s*(\[[^\]]*\]|:\w+|["'][^"']+["'])"#).unwrap();
+
+    for line in gemfile_content.lines().map(strip_comment) {
+        if let Some(captures) = group_block_regexp.captures(line) {
+            blocks.push(parse_group_names(captures.get(1).unwrap().as_str()));
+        } else if end_regexp.is_match(line) {
+            blocks.pop();
+        } else if let Some(captures) = gem_regexp.captures(line) {
+            let mut groups: Vec<String> = blocks.iter().flatten().cloned().collect();
+            if let Some(inline) = inline_groups_regexp.captures(line) {
+                groups.extend(parse_group_names(inline.get(1).unwrap().as_str()));
+            }
+            if groups.is_empty() {
+                groups.push(String::from(DEFAULT_GROUP));
+            }
+
+            let gem_groups = result
+                .entry(String::from(captures.get(1).unwrap().as_str()))
+                .or_default();
+            gem_groups.extend(groups);
+            gem_groups.sort_unstable();
+            gem_groups.dedup();
+        } else if other_block_regexp.is_match(line) {
+            blocks.push(Vec::new());
+        }
+    }
+
+    result
+}
+
+///
+/// Adds `cdx:bundler:groups` property with comma separated groups list
+/// to every gem declared in Gemfile
+///
+pub(crate) fn mark_groups(gems: &mut [Gemspec], groups: &HashMap<String, Vec<String>>) {
+    for gem in gems.iter_mut() {
+        if let Some(gem_groups) = groups.get(&gem.name) {
+            gem.properties
+                .push(Property::new(GROUPS_PROPERTY, gem_groups.join(",")));
+        }
+    }
+}
+
+///
+/// Returns direct dependencies, which groups pass filters: gem should be
+/// in any of `only` groups, if they are given, and not only in `exclude`
+/// groups. Gems missing in Gemfile belong to the `default` group
+///
+pub(crate) fn select_gems(
+    direct: &[String],
+    groups: &HashMap<String, Vec<String>>,
+    only: &[String],
+    exclude: &[String],
+) -> Vec<String> {
+    let default_groups = vec![String::from(DEFAULT_GROUP)];
+
+    direct
+        .iter()
+        .filter(|name| {
+            let gem_groups = groups.get(*name).unwrap_or(&default_groups);
+
+            (only.is_empty() || gem_groups.iter().any(|group| only.contains(group)))
+                && !gem_groups.iter().all(|group| exclude.contains(group))
+        })
+        .cloned()
+        .collect()
+}
+
+///
+/// Returns groups, which gems are skipped: given `exclude` groups and,
+/// unless dev dependencies are included, development and test groups.
+/// Dev groups requested with `only` are not skipped
+///
+pub(crate) fn excluded_groups(
+    exclude: &[String],
+    only: &[String],
+    include_dev: bool,
+) -> Vec<String> {
+    let mut excluded = exclude.to_vec();
+    if !include_dev {
+        excluded.extend(
+            DEV_GROUPS
+                .iter()
+                .map(|group| String::from(*group))
+                .filter(|group| !only.contains(group) && !exclude.contains(group)),
+        );
+    }
+
+    excluded
+}
+
+//
+// Extracts group names from `:development, :test`, `[:test]` or `"test"`
+//
+fn parse_group_names(value: &str) -> Vec<String> {
+    let name_regexp = Regex::new(r#":(\w+)|["']([^"']+)["']"#).unwrap();
+
+    name_regexp
+        .captures_iter(value)
+        .filter_map(|captures| captures.get(1).or_else(|| captures.get(2)))
+        .map(|name| String::from(name.as_str()))
+        .collect()
+}
+
+//
+// Drops trailing comment, Gemfile gems names never contain `#`
+//
+fn strip_comment(line: &str) -> &str {
+    match line.split_once('#') {
+        Some((code, _)) => code,
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GEMFILE: &str = r#"
+source "https://rubygems.org"
+
+gem "rails", "~> 7.1.1"
+gem "pg", ">= 1.1" # database
+
+group :development, :test do
+  gem "debug", platforms: %i[ mri windows ]
+end
+
+group :test do
+  gem "capybara"
+  platforms :jruby do
+    gem "jdbc-sqlite3"
+  end
+end
+
+gem "rubocop", require: false, group: :development
+gem "brakeman", groups: [:development, :ci]
+"#;
+
+    #[test]
+    fn test_parse_groups() {
+        let result = parse_groups(GEMFILE);
+
+        assert_eq!(result.len(), 7);
+        assert_eq!(result["rails"], vec!["default"]);
+        assert_eq!(result["pg"], vec!["default"]);
+        assert_eq!(result["debug"], vec!["development", "test"]);
+        assert_eq!(result["capybara"], vec!["test"]);
+        assert_eq!(result["jdbc-sqlite3"], vec!["test"]);
+        assert_eq!(result["rubocop"], vec!["development"]);
+        assert_eq!(result["brakeman"], vec!["ci", "development"]);
+    }
+
+    #[test]
+    fn test_select_gems() {
+        let groups = parse_groups(GEMFILE);
+        let direct: Vec<String> = ["rails", "debug", "capybara", "brakeman", "puma"]
+            .map(String::from)
+            .to_vec();
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(select_gems(&direct, &groups, &[], &[]), direct);
+        assert_eq!(
+            select_gems(&direct, &groups, &[], &names(&["development", "test"])),
+            ["rails", "brakeman", "puma"]
+        );
+        assert_eq!(
+            select_gems(&direct, &groups, &names(&["test"]), &[]),
+            ["debug", "capybara"]
+        );
+    }
+
+    #[test]
+    fn test_excluded_groups() {
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            excluded_groups(&[], &[], false),
+            names(&["development", "test"])
+        );
+        assert!(excluded_groups(&[], &[], true).is_empty());
+        assert_eq!(
+            excluded_groups(&names(&["ci", "test"]), &names(&["development"]), false),
+            names(&["ci", "test"])
+        );
+        assert_eq!(excluded_groups(&names(&["ci"]), &[], true), names(&["ci"]));
+    }
+
+    #[test]
+    fn test_select_gems_dev_dependencies() {
+        let groups = parse_groups(GEMFILE);
+        let direct: Vec<String> = ["rails", "debug", "capybara", "rubocop", "brakeman"]
+            .map(String::from)
+            .to_vec();
+
+        let production = select_gems(&direct, &groups, &[], &excluded_groups(&[], &[], false));
+        let full = select_gems(&direct, &groups, &[], &excluded_groups(&[], &[], true));
+
+        assert_eq!(production, ["rails", "brakeman"]);
+        assert_eq!(full, direct);
+    }
+
+    #[test]
+    fn test_mark_groups() {
+        let groups = parse_groups(GEMFILE);
+        let mut gems = vec![
+            Gemspec {
+                name: String::from("capybara"),
+                ..Default::default()
+            },
+            Gemspec {
+                name: String::from("nokogiri"),
+                ..Default::default()
+            },
+        ];
+
+        mark_groups(&mut gems, &groups);
+
+        assert_eq!(
+            gems[0].properties,
+            vec![Property::new(GROUPS_PROPERTY, String::from("test"))]
+        );
+        assert!(gems[1].properties.is_empty());
+    }
+}
@@ -0,0 +1,39 @@
+// Gzip compression of bom files
+
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+///
+/// Compresses data to gzip format
+///
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // writing to vector could not fail
+    encoder.write_all(data).unwrap();
+
+    encoder.finish().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_round_trip() {
+        let bom = r#"{"components":[{"name":"rake","purl":"pkg:gem/rake@13.0.6"},{"name":"rack","purl":"pkg:gem/rack@3.0.0"}]}"#
+            .repeat(50);
+
+        let compressed = compress(bom.as_bytes());
+        let mut decompressed = String::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+
+        assert!(compressed.len() < bom.len() / 10);
+        assert_eq!(decompressed, bom);
+    }
+}
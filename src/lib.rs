@@ -0,0 +1,1506 @@
+use std::collections::HashMap;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::{stream, StreamExt};
+use reqwest_middleware::ClientWithMiddleware;
+use url::Url;
+
+mod archive;
+mod artifactory;
+mod bom_se;
+mod bundler;
+mod cache;
+mod client;
+mod config;
+mod depth;
+mod error_report;
+mod errors;
+mod gem;
+mod groups;
+mod gzip;
+mod logger;
+mod nexus;
+mod progress;
+mod purl;
+mod repository;
+mod stale;
+#[cfg(test)]
+mod test_support;
+mod timestamp;
+
+pub use config::Format;
+pub use errors::{FetchPackageError, NexusError, PurlError, RepoError, RunError};
+pub use gem::Gemspec;
+
+const CONCURRENT_REQUESTS: usize = 50;
+
+///
+/// Options of bom generation for library usage
+///
+#[derive(Debug, Clone)]
+pub struct BomOptions {
+    // Output bom format
+    pub format: Format,
+    // Gems registry with rubygems.org compatible API
+    pub registry_url: Url,
+}
+
+///
+/// Generates bom file content for given Gemfile.lock content. Gems
+/// information is fetched from gems registry, gems which could not be
+/// fetched are omitted
+///
+pub async fn generate_bom(gemfile_contents: &str, options: BomOptions) -> Result<String> {
+    if options.format == Format::Protobuf {
+        bail!("Protobuf bom is binary and could not be returned as string");
+    }
+
+    let mut specs = bundler::parse_gemfile(gemfile_contents.to_string());
+    specs.gems.retain(bundler::Source::has_version);
+
+    let client_options = client::ClientOptions::default();
+    let client = client::get_client(&client_options)?;
+    let registry =
+        gem::Registry::new(options.registry_url).with_retries(client_options.registry_retries());
+    let (mut gems, _) = fetch_gems_info(
+        &client,
+        specs.gems,
+        &registry,
+        &cache::Cache::default(),
+        false,
+        Duration::ZERO,
+        &progress::Progress::new(0, false),
+    )
+    .await;
+    gems.extend(get_git_gems(&specs.git_gems));
+
+    bom_se::sort_components(&mut gems);
+    let serialize_options = bom_se::SerializeOptions {
+        environment: specs.environment,
+        timestamp: timestamp::bom_timestamp(None)?,
+        ..Default::default()
+    };
+    let bom = bom_se::serialize(&gems, &options.format, &serialize_options)?;
+
+    Ok(String::from_utf8(bom)?)
+}
+
+///
+/// Runs command line application: parses arguments, builds bom file and
+/// checks packages in Nexus if requested
+///
+pub async fn run() -> Result<()> {
+    let params = config::prepare_env();
+
+    let client = client::get_client(&params.client_options)?;
+    let cache = cache::Cache::new(params.cache_dir.clone(), params.offline)
+        .with_refresh(params.prefer_cache);
+    let registry = gem::Registry::new(params.registry_url.clone())
+        .with_credentials(params.registry_credentials.clone())
+        .with_compact_index(params.compact_index)
+        .with_target_platform(params.target_platform.clone())
+        .with_purl_qualifiers(params.purl_qualifiers)
+        .with_retries(params.client_options.registry_retries());
+
+    let timestamp = timestamp::bom_timestamp(params.timestamp)?;
+    let targets = get_bom_targets(&params)?;
+    for target in &targets {
+        check_output_dir(&target.output_file_name)?;
+    }
+    for target in targets {
+        build_bom(&target, &client, &registry, &cache, &params, &timestamp).await?;
+    }
+
+    if params.prefer_cache {
+        gem::refresh_cache(&client, &registry, &cache, CONCURRENT_REQUESTS).await;
+    }
+
+    Ok(())
+}
+
+///
+/// Gemfile.lock files described by one bom file
+///
+#[derive(Debug, PartialEq)]
+struct BomTarget {
+    lock_files: Vec<PathBuf>,
+    // Gemfile.lock in directory of described project
+    project: PathBuf,
+    output_file_name: PathBuf,
+}
+
+///
+/// Gems collected from one Gemfile.lock
+///
+struct LockfileGems {
+    gems: Vec<gem::Gemspec>,
+    failed: Vec<error_report::FetchFailure>,
+    direct: Vec<String>,
+    environment: bundler::Environment,
+}
+
+//
+// Builds list of bom files to create. In recursive mode all Gemfile.lock
+// files under input directory are described by one merged bom file or
+// by bom file next to each of them
+//
+fn get_bom_targets(params: &config::Params) -> Result<Vec<BomTarget>> {
+    if !params.recursive {
+        return Ok(vec![BomTarget {
+            lock_files: vec![params.input_file_name.clone()],
+            project: params.input_file_name.clone(),
+            output_file_name: params.output_file_name.clone(),
+        }]);
+    }
+
+    if config::is_stdin_path(&params.input_file_name) {
+        bail!("Recursive search is not possible for Gemfile.lock from stdin");
+    }
+    if archive::split_archive_path(&params.input_file_name).is_some() {
+        bail!("Recursive search is not possible for Gemfile.lock from archive");
+    }
+    let root = params.input_file_name.parent().unwrap_or(Path::new("."));
+    let lock_files = find_lockfiles(root)?;
+    if lock_files.is_empty() {
+        bail!("No Gemfile.lock files found in {}", root.display());
+    }
+
+    let targets = match params.recursive_output {
+        config::RecursiveOutput::Merged => vec![BomTarget {
+            lock_files,
+            project: params.input_file_name.clone(),
+            output_file_name: params.output_file_name.clone(),
+        }],
+        config::RecursiveOutput::PerFile => lock_files
+            .into_iter()
+            .map(|lock_file| BomTarget {
+                output_file_name: lock_file
+                    .with_file_name(format!("bom.{}", params.format.extension())),
+                project: lock_file.clone(),
+                lock_files: vec![lock_file],
+            })
+            .collect(),
+    };
+
+    Ok(targets)
+}
+
+//
+// Finds all Gemfile.lock files in directory tree. Hidden, vendor and
+// node_modules directories are skipped, they contain installed packages
+//
+fn find_lockfiles(root: &Path) -> Result<Vec<PathBuf>> {
+    if !root.is_dir() {
+        bail!("Specified path {} is not a directory", root.display());
+    }
+
+    let mut lock_files = Vec::new();
+    let mut directories = vec![root.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+
+        for entry in entries.map_while(Result::ok) {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "vendor" && name != "node_modules" {
+                    directories.push(path);
+                }
+            } else if name == "Gemfile.lock" {
+                lock_files.push(path);
+            }
+        }
+    }
+    lock_files.sort();
+
+    Ok(lock_files)
+}
+
+//
+// Builds and writes bom file for given Gemfile.lock files. Components
+// found in several files are kept once
+//
+async fn build_bom(
+    target: &BomTarget,
+    client: &ClientWithMiddleware,
+    registry: &gem::Registry,
+    cache: &cache::Cache,
+    params: &config::Params,
+    timestamp: &str,
+) -> Result<()> {
+    let mut gems = Vec::new();
+    let mut failed = Vec::new();
+    let mut direct = Vec::new();
+    let mut environment = None;
+
+    for lock_file in &target.lock_files {
+        let collected = collect_gems(lock_file, client, registry, cache, params).await?;
+        gems.extend(collected.gems);
+        failed.extend(collected.failed);
+        direct.extend(collected.direct);
+        environment.get_or_insert(collected.environment);
+    }
+    let mut gems = bom_se::merge_components(gems);
+
+    if params.fail_on_empty {
+        if let Err(error) = check_not_empty(&gems) {
+            log::error!(target: "empty_bom", "{}", error);
+            return Err(RunError::EmptyBom(error.to_string()).into());
+        }
+    }
+
+    depth::mark_scopes(&mut gems, &direct);
+    gems.iter_mut()
+        .for_each(|gem| gem.component_type = params.component_type);
+    if params.component_depth {
+        depth::mark_depths(&mut gems, &direct);
+    }
+
+    let stale_gems = match params.max_age {
+        Some(max_age) => stale::mark_stale(&mut gems, max_age, timestamp::today()),
+        None => vec![],
+    };
+
+    bom_se::sort_components(&mut gems);
+    if params.normalize_description {
+        bom_se::normalize_descriptions(&mut gems);
+    }
+    if let Some(pattern) = &params.redact_pattern {
+        bom_se::redact_descriptions(&mut gems, pattern);
+    }
+    check_purls(&gems, params.strict)?;
+    let options = bom_se::SerializeOptions {
+        annotations: params.annotations.clone(),
+        environment: environment.unwrap_or_default(),
+        extra_fields: params.extra_bom_fields.clone(),
+        root: params
+            .root_type
+            .map(|root_type| build_root(root_type, &target.project)),
+        timestamp: timestamp.to_string(),
+    };
+    if params.validate || params.compress {
+        let bom_file = bom_se::serialize(&gems, &params.format, &options)?;
+        if params.validate {
+            check_schema(&bom_file, &params.format, &params.extra_bom_fields)?;
+        }
+
+        write_bomfile(
+            &target.output_file_name,
+            bom_file,
+            params.output_mode,
+            params.compress,
+        )?;
+    } else {
+        stream_bomfile(&target.output_file_name, params.output_mode, |output| {
+            bom_se::serialize_to(&gems, &params.format, &options, output)
+        })?;
+    }
+
+    if let Some(report_path) = &params.error_report {
+        fs::write(report_path, error_report::build_report(&failed)?)?;
+    }
+
+    if params.fail_on_stale && !stale_gems.is_empty() {
+        bail!("Found stale gems: {}", stale_gems.join(", "));
+    }
+
+    if params.fail_on_error && !failed.is_empty() {
+        let labels: Vec<String> = failed
+            .iter()
+            .map(error_report::FetchFailure::label)
+            .collect();
+        bail!("Could not fetch info about gems: {}", labels.join(", "));
+    }
+
+    check_repository(&gems, params).await?;
+
+    Ok(())
+}
+
+//
+// Parses Gemfile.lock and fetches info about its gems
+//
+async fn collect_gems(
+    lock_file: &PathBuf,
+    client: &ClientWithMiddleware,
+    registry: &gem::Registry,
+    cache: &cache::Cache,
+    params: &config::Params,
+) -> Result<LockfileGems> {
+    let content = read_gemfilelock(lock_file)?;
+    let mut specs = bundler::parse_gemfile(content);
+    if let Err(error) = check_parsed_gems(&specs) {
+        if params.fail_on_empty {
+            log::error!(target: "empty_bom", "{}: {}", lock_file.display(), error);
+            return Err(RunError::EmptyBom(format!("{}: {}", lock_file.display(), error)).into());
+        }
+        log::warn!(target: "empty_bom", "{}: {}", lock_file.display(), error);
+    } else if specs.direct.is_empty() {
+        log::warn!(
+            target: "empty_bom",
+            "{}: DEPENDENCIES section is missing or empty, all gems are treated as transitive",
+            lock_file.display()
+        );
+    }
+    let gemfile_groups = read_gemfile(lock_file).map(|gemfile| groups::parse_groups(&gemfile));
+    filter_groups(&mut specs, gemfile_groups.as_ref(), params, lock_file);
+    if let Some(key) = params.dedupe_by {
+        specs.dedupe(key);
+    }
+    if let Some(pattern) = &params.name_filter {
+        specs.filter_names(pattern);
+    }
+    if !params.include_versionless {
+        specs.gems.retain(bundler::Source::has_version);
+    }
+    specs.order_gems(params.fetch_order);
+
+    let denied = specs.find_denied_sources(&params.deny_sources);
+    if !denied.is_empty() {
+        bail!("Found gems from denied sources: {}", denied.join(", "));
+    }
+
+    let progress = progress::Progress::new(specs.gems.len(), params.progress);
+    let (mut gems, failed) = fetch_gems_info(
+        client,
+        specs.gems,
+        registry,
+        cache,
+        params.group_from_source,
+        params.concurrency_warmup,
+        &progress,
+    )
+    .await;
+    progress.finish();
+    let labels: Vec<String> = failed
+        .iter()
+        .map(error_report::FetchFailure::label)
+        .collect();
+    if labels.is_empty() {
+        log::info!(target: "fetch_summary", "{}", fetch_summary(gems.len(), &labels));
+    } else {
+        log::warn!(target: "fetch_summary", "{}", fetch_summary(gems.len(), &labels));
+    }
+    gems.extend(get_git_gems(&specs.git_gems));
+
+    if params.evidence_occurrences {
+        let location = lock_file.display().to_string();
+        gems.iter_mut()
+            .for_each(|gem| gem.add_occurrence(&location));
+    }
+
+    if let Some(gemfile_groups) = &gemfile_groups {
+        groups::mark_groups(&mut gems, gemfile_groups);
+    }
+
+    Ok(LockfileGems {
+        gems,
+        failed,
+        direct: specs.direct,
+        environment: specs.environment,
+    })
+}
+
+//
+// This is a core function. It spawns threads and dispatches it
+// to make requests and fetch all gems info from gems registry.
+// Returns received gems and failures of failed ones. Progress bar
+// is advanced as each request completes
+//
+async fn fetch_gems_info(
+    client: &ClientWithMiddleware,
+    specs: Vec<bundler::Source>,
+    registry: &gem::Registry,
+    cache: &cache::Cache,
+    group_from_source: bool,
+    warmup: Duration,
+    progress: &progress::Progress,
+) -> (Vec<gem::Gemspec>, Vec<error_report::FetchFailure>) {
+    let gem_specs_results = stream::iter(specs.into_iter().enumerate())
+        .map(|(index, source)| async move {
+            let delay = warmup_delay(index, CONCURRENT_REQUESTS, warmup);
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+
+            let source_info = source.get_source();
+            let result = gem::get_gem(client, source_info, source.get_remote(), registry, cache)
+                .await
+                .map(|gem| gem.with_dependencies(source.get_dependencies()))
+                .map(|gem| {
+                    if group_from_source {
+                        gem.with_group(source.get_remote_host())
+                    } else {
+                        gem
+                    }
+                });
+
+            progress.inc();
+            let (name, version, _) = source_info;
+            result.map_err(|error| error_report::FetchFailure::new(name, version, &error))
+        })
+        .buffer_unordered(CONCURRENT_REQUESTS)
+        .collect::<Vec<Result<gem::Gemspec, error_report::FetchFailure>>>()
+        .await;
+
+    let mut gem_specs = Vec::new();
+    let mut failed = Vec::new();
+    for result in gem_specs_results {
+        match result {
+            Ok(gem) => gem_specs.push(gem),
+            Err(failure) => {
+                log::warn!(target: "fetch_error", "{}", failure.message());
+                failed.push(failure);
+            }
+        }
+    }
+    failed.sort_by_key(error_report::FetchFailure::label);
+
+    (gem_specs, failed)
+}
+
+//
+// Builds metadata component for project, which contains Gemfile.lock.
+// Project is named after its directory
+//
+fn build_root(root_type: bom_se::ComponentType, lock_file_name: &Path) -> bom_se::RootComponent {
+    let name = lock_file_name
+        .parent()
+        .and_then(|dir| fs::canonicalize(dir).ok())
+        .and_then(|dir| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| String::from("project"));
+
+    bom_se::RootComponent {
+        component_type: root_type,
+        bom_ref: name.clone(),
+        name,
+    }
+}
+
+//
+// Builds end of run report about fetched gems
+//
+fn fetch_summary(received: usize, failed: &[String]) -> String {
+    let summary = format!(
+        "Received info about {received} gems, failed {} gems",
+        failed.len()
+    );
+
+    if failed.is_empty() {
+        summary
+    } else {
+        format!("{summary}: {}", failed.join(", "))
+    }
+}
+
+//
+// Builds gems from GIT sections of Gemfile.lock. Gems without revision
+// or version are skipped
+//
+fn get_git_gems(sources: &[bundler::Source]) -> Vec<gem::Gemspec> {
+    sources
+        .iter()
+        .filter_map(|source| {
+            let revision = source.get_revision()?;
+            let (name, version, platform) = source.get_source();
+
+            Some(
+                gem::get_git_gem((name, version?, platform), source.get_remote(), revision)
+                    .with_dependencies(source.get_dependencies()),
+            )
+        })
+        .collect()
+}
+
+//
+// Computes start delay of request with given index. Requests of the first
+// batch are spread evenly over warmup duration, so full concurrency is
+// reached gradually. Other requests start without delay
+//
+fn warmup_delay(index: usize, concurrency: usize, warmup: Duration) -> Duration {
+    if index >= concurrency {
+        return Duration::ZERO;
+    }
+
+    warmup * index as u32 / concurrency as u32
+}
+
+//
+// Try to find Gemfile.lock file and return it content, or read it from
+// stdin or tar archive. If it not possible to open file, it aborts process
+//
+fn read_gemfilelock(file_name: &PathBuf) -> Result<String> {
+    if config::is_stdin_path(file_name) {
+        let mut content = String::new();
+        if io::stdin().read_to_string(&mut content).is_err() {
+            bail!("Could not read Gemfile.lock content from stdin");
+        }
+
+        return Ok(content);
+    }
+
+    if let Some((archive, entry)) = archive::split_archive_path(file_name) {
+        return archive::read_lockfile(&archive, &entry);
+    }
+
+    let gemfile = fs::read_to_string(file_name);
+
+    match gemfile {
+        Ok(content) => Ok(content),
+        Err(error) => match error.kind() {
+            ErrorKind::NotFound => bail!("Specified path does not contains Gemfile.lock file"),
+            ErrorKind::PermissionDenied => bail!("Permission denied to open Gemfile.lock file"),
+            _ => bail!("Unknown error while reading file"),
+        },
+    }
+}
+
+//
+// Keeps gems from selected Gemfile groups and their dependencies. Gems
+// only from development and test groups are skipped, unless dev
+// dependencies are included. Without Gemfile or DEPENDENCIES section
+// groups are unknown and all gems are kept
+//
+fn filter_groups(
+    specs: &mut bundler::Gemfile,
+    gemfile_groups: Option<&HashMap<String, Vec<String>>>,
+    params: &config::Params,
+    lock_file: &Path,
+) {
+    let has_filters = !params.only_groups.is_empty() || !params.exclude_groups.is_empty();
+    let Some(gemfile_groups) = gemfile_groups else {
+        if has_filters {
+            log::warn!(
+                "Gemfile is not found next to {}, groups filters are ignored",
+                lock_file.display()
+            );
+        }
+        return;
+    };
+    if !has_filters && specs.direct.is_empty() {
+        return;
+    }
+
+    let exclude_groups = groups::excluded_groups(
+        &params.exclude_groups,
+        &params.only_groups,
+        params.include_dev_dependencies,
+    );
+    if params.only_groups.is_empty() && exclude_groups.is_empty() {
+        return;
+    }
+
+    let selected = groups::select_gems(
+        &specs.direct,
+        gemfile_groups,
+        &params.only_groups,
+        &exclude_groups,
+    );
+    specs.retain_dependencies_of(&selected);
+}
+
+//
+// Checks that bom file could be written, before gems info is fetched.
+// Directory of regular output file should exist and be writable, as bom
+// file is created there via temporary file. Existing FIFOs and devices
+// are written directly and are not checked
+//
+fn check_output_dir(file_name: &Path) -> Result<()> {
+    if fs::metadata(file_name).is_ok_and(|metadata| !metadata.is_file()) {
+        return Ok(());
+    }
+
+    let dir = match file_name.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let probe = dir.join(format!(".cyclonedx-rs-gem-{}.check", process::id()));
+
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(error) => match error.kind() {
+            ErrorKind::NotFound => bail!("Output directory {} does not exist", dir.display()),
+            ErrorKind::PermissionDenied => {
+                bail!("Permission denied to write bom file to {}", dir.display())
+            }
+            _ => bail!("Could not write bom file to {}: {}", dir.display(), error),
+        },
+    }
+}
+
+//
+// Reads Gemfile located next to Gemfile.lock, in the same directory or
+// archive directory. Gemfile is optional, it is used only for groups
+// info. There is no Gemfile for stdin input
+//
+fn read_gemfile(lock_file_name: &Path) -> Option<String> {
+    if config::is_stdin_path(lock_file_name) {
+        return None;
+    }
+
+    if let Some((archive, entry)) = archive::split_archive_path(lock_file_name) {
+        let gemfile = archive::read_gemfile(&archive, &entry).ok().flatten();
+        if gemfile.is_none() {
+            log::warn!(
+                target: "gemfile_groups",
+                "Gemfile is not found next to {} in archive, groups are unavailable and gems from all groups are included",
+                lock_file_name.display()
+            );
+        }
+
+        return gemfile;
+    }
+
+    fs::read_to_string(lock_file_name.with_file_name("Gemfile")).ok()
+}
+
+//
+// Checks that bom file will contain at least one component. Empty bom
+// usually means wrong path, broken Gemfile.lock or failed requests
+//
+fn check_not_empty(gems: &[gem::Gemspec]) -> Result<()> {
+    if gems.is_empty() {
+        bail!("No components found, bom file would be empty. Check Gemfile.lock path and content");
+    }
+
+    Ok(())
+}
+
+//
+// Checks that Gemfile.lock has at least one gem. Empty GEM section or
+// its absence usually means parsing or path mistake
+//
+fn check_parsed_gems(specs: &bundler::Gemfile) -> Result<()> {
+    if specs.gems.is_empty() && specs.git_gems.is_empty() {
+        bail!("No gems found in Gemfile.lock, GEM section is missing or empty");
+    }
+
+    Ok(())
+}
+
+//
+// Validates json bom file against CycloneDX schema. Bom files in other
+// formats are not validated
+//
+fn check_schema(bom_file: &[u8], format: &Format, extra_fields: &[(String, String)]) -> Result<()> {
+    if *format != Format::Json {
+        log::warn!(target: "schema", "Only json bom file could be validated");
+        return Ok(());
+    }
+
+    let extra_fields: Vec<String> = extra_fields.iter().map(|(key, _)| key.clone()).collect();
+    let violations = bom_se::validate(&String::from_utf8_lossy(bom_file), &extra_fields);
+    if !violations.is_empty() {
+        bail!(
+            "Bom file does not match CycloneDX schema:\n{}",
+            violations.join("\n")
+        );
+    }
+
+    Ok(())
+}
+
+//
+// Validates purls of all components. Malformed purls fail the run in
+// strict mode, otherwise they are only reported
+//
+fn check_purls(gems: &[gem::Gemspec], strict: bool) -> Result<()> {
+    let invalid = purl::find_invalid(gems);
+    if invalid.is_empty() {
+        return Ok(());
+    }
+
+    if strict {
+        let errors: Vec<String> = invalid.iter().map(ToString::to_string).collect();
+        bail!("{}", errors.join("\n"));
+    }
+    invalid
+        .iter()
+        .for_each(|error| log::warn!(target: "malformed_purl", "{}", error));
+
+    Ok(())
+}
+
+//
+// Try to write on disk bom.json or bom.xml file. Regular files are written
+// to temporary file first and then renamed, so readers never see partially
+// written bom. FIFOs and devices could not be renamed over, they are
+// written directly. Renamed file gets explicit mode if given, otherwise
+// permissions of replaced file, otherwise ones derived from process umask.
+// Content is compressed with gzip if requested, also for FIFOs and devices
+//
+fn write_bomfile(
+    file_name: &PathBuf,
+    content: impl AsRef<[u8]>,
+    mode: Option<u32>,
+    compress: bool,
+) -> Result<()> {
+    let compressed;
+    let content = if compress {
+        compressed = gzip::compress(content.as_ref());
+        compressed.as_slice()
+    } else {
+        content.as_ref()
+    };
+
+    stream_bomfile(file_name, mode, |output| Ok(output.write_all(content)?))
+}
+
+//
+// Writes bom file as `write_bomfile` does, but content is written by
+// `write` function directly to buffered file, so large bom files are not
+// kept in memory
+//
+fn stream_bomfile(
+    file_name: &PathBuf,
+    mode: Option<u32>,
+    write: impl FnOnce(&mut dyn Write) -> Result<()>,
+) -> Result<()> {
+    let metadata = fs::metadata(file_name).ok();
+    if metadata
+        .as_ref()
+        .is_some_and(|metadata| !metadata.is_file())
+    {
+        let file = OpenOptions::new().write(true).open(file_name)?;
+        let mut output = BufWriter::new(file);
+        write(&mut output)?;
+        output.flush()?;
+
+        return Ok(());
+    }
+
+    let permissions = match mode {
+        Some(mode) => mode_permissions(mode),
+        None => metadata.map(|metadata| metadata.permissions()),
+    };
+
+    let mut temp_name = file_name.clone().into_os_string();
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp_name = PathBuf::from(temp_name);
+
+    let result = File::create(&temp_name)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| {
+            let mut output = BufWriter::new(file);
+            write(&mut output)?;
+            let file = output.into_inner().map_err(|error| error.into_error())?;
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+
+            Ok(fs::rename(&temp_name, file_name)?)
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_name);
+    }
+
+    result
+}
+
+#[cfg(unix)]
+fn mode_permissions(mode: u32) -> Option<fs::Permissions> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Some(fs::Permissions::from_mode(mode))
+}
+
+// file modes are supported only on unix
+#[cfg(not(unix))]
+fn mode_permissions(_mode: u32) -> Option<fs::Permissions> {
+    None
+}
+
+//
+// Checks gems presence in Nexus or Artifactory, if one of them is set
+//
+async fn check_repository(gems: &[gem::Gemspec], params: &config::Params) -> Result<()> {
+    let Some(checker) = build_checker(params)? else {
+        return Ok(());
+    };
+    let (result, errors) = repository::check_packages(gems, checker.as_ref()).await;
+
+    if let Some(report_path) = &params.nexus_report {
+        let report = repository::build_report(&result, params.nexus_report_all)?;
+        fs::write(report_path, report)?;
+    }
+
+    let not_found: Vec<repository::RepoResult> =
+        result.into_iter().filter(|item| item.is_absent()).collect();
+
+    if not_found.is_empty() && errors.is_empty() {
+        log::info!(target: "nexus_all_found", "All packages exists in {} repository.", checker.name());
+    }
+    for package in &not_found {
+        log::info!(target: "nexus_not_found", "Not found in {}. {}", checker.name(), package);
+    }
+    if !errors.is_empty() {
+        log::warn!(
+            target: "nexus_error",
+            "Could not check {} packages in {} repository",
+            errors.len(),
+            checker.name()
+        );
+    }
+
+    if params.fail_on_missing {
+        check_not_missing(&not_found, errors.len(), checker.name())?;
+    }
+
+    Ok(())
+}
+
+//
+// Builds checker for configured repository, Nexus and Artifactory
+// urls are mutually exclusive
+//
+fn build_checker(
+    params: &config::Params,
+) -> Result<Option<Box<dyn repository::RepositoryChecker>>> {
+    if let Some(url) = &params.nexus_url {
+        let nexus = nexus::Nexus::new(url, &params.nexus_format, &params.client_options)?
+            .with_credentials(params.nexus_credentials.clone());
+
+        return Ok(Some(Box::new(nexus)));
+    }
+
+    if let Some(url) = &params.artifactory_url {
+        let artifactory = artifactory::Artifactory::new(
+            url,
+            params.artifactory_repo.as_deref(),
+            &params.client_options,
+        )?
+        .with_credentials(params.artifactory_credentials.clone());
+
+        return Ok(Some(Box::new(artifactory)));
+    }
+
+    Ok(None)
+}
+
+//
+// Checks that all packages exist in repository
+//
+fn check_not_missing(
+    not_found: &[repository::RepoResult],
+    failed: usize,
+    repository: &str,
+) -> Result<()> {
+    if !not_found.is_empty() {
+        bail!("{} packages not found in {repository}", not_found.len());
+    }
+    if failed > 0 {
+        bail!("{failed} packages could not be checked in {repository}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_summary() {
+        let failed = vec![String::from("nokogiri 1.16.5"), String::from("rake")];
+
+        assert_eq!(
+            fetch_summary(3, &[]),
+            "Received info about 3 gems, failed 0 gems"
+        );
+        assert_eq!(
+            fetch_summary(1, &failed),
+            "Received info about 1 gems, failed 2 gems: nokogiri 1.16.5, rake"
+        );
+    }
+
+    #[test]
+    fn test_find_lockfiles() {
+        let root = std::env::temp_dir().join(format!("cyclonedx-lockfiles-{}", process::id()));
+        for dir in ["billing", "auth/api", "auth/vendor/bundle", ".git", "docs"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for dir in ["", "billing", "auth/api", "auth/vendor/bundle", ".git"] {
+            fs::write(root.join(dir).join("Gemfile.lock"), "").unwrap();
+        }
+
+        let result = find_lockfiles(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                root.join("Gemfile.lock"),
+                root.join("auth/api/Gemfile.lock"),
+                root.join("billing/Gemfile.lock"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bom_targets_per_file() {
+        let root = std::env::temp_dir().join(format!("cyclonedx-targets-{}", process::id()));
+        fs::create_dir_all(root.join("billing")).unwrap();
+        fs::write(root.join("billing/Gemfile.lock"), "").unwrap();
+        let path = root.to_str().unwrap();
+
+        let merged = get_bom_targets(&config::parse_test_params(&["-p", path, "--recursive"]));
+        let per_file = get_bom_targets(&config::parse_test_params(&[
+            "-p",
+            path,
+            "--recursive",
+            "--recursive-output",
+            "per-file",
+        ]));
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            merged.unwrap(),
+            vec![BomTarget {
+                lock_files: vec![root.join("billing/Gemfile.lock")],
+                project: root.join("Gemfile.lock"),
+                output_file_name: root.join("bom.json"),
+            }]
+        );
+        assert_eq!(
+            per_file.unwrap(),
+            vec![BomTarget {
+                lock_files: vec![root.join("billing/Gemfile.lock")],
+                project: root.join("billing/Gemfile.lock"),
+                output_file_name: root.join("billing/bom.json"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check_schema() {
+        let corrupted = r#"{"bomFormat": "SPDX", "specVersion": "1.5"}"#;
+
+        let json = check_schema(corrupted.as_bytes(), &Format::Json, &[]);
+        let xml = check_schema(corrupted.as_bytes(), &Format::Xml, &[]);
+
+        assert!(json.is_err_and(|error| error.to_string().contains("/bomFormat: ")));
+        assert!(xml.is_ok());
+    }
+
+    #[test]
+    fn test_build_root() {
+        let dir = std::env::temp_dir().join("cyclonedx-root-test");
+        fs::create_dir_all(&dir).unwrap();
+
+        let root = build_root(bom_se::ComponentType::Library, &dir.join("Gemfile.lock"));
+
+        assert_eq!(root.name, "cyclonedx-root-test");
+        assert_eq!(root.component_type, bom_se::ComponentType::Library);
+    }
+
+    #[test]
+    fn test_check_not_empty_when_no_gems() {
+        let gems: Vec<gem::Gemspec> = Vec::new();
+
+        let result = check_not_empty(&gems);
+
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string().as_str(),
+            "No components found, bom file would be empty. Check Gemfile.lock path and content"
+        );
+    }
+
+    #[test]
+    fn test_check_parsed_gems() {
+        let empty = bundler::parse_gemfile(String::from(
+            "GEM\n  remote: https://rubygems.org/\n  specs:\n\nPLATFORMS\n  ruby\n",
+        ));
+        let missing = bundler::parse_gemfile(String::from("PLATFORMS\n  ruby\n"));
+        let filled = bundler::parse_gemfile(String::from(
+            "GEM\n  remote: https://rubygems.org/\n  specs:\n    rake (13.0.6)\n",
+        ));
+
+        assert_eq!(
+            check_parsed_gems(&empty).unwrap_err().to_string(),
+            "No gems found in Gemfile.lock, GEM section is missing or empty"
+        );
+        assert!(check_parsed_gems(&missing).is_err());
+        assert!(check_parsed_gems(&filled).is_ok());
+    }
+
+    #[test]
+    fn test_check_output_dir() {
+        let dir = std::env::temp_dir().join("cyclonedx-rs-gem-output-dir");
+        fs::create_dir_all(&dir).unwrap();
+
+        let existing = check_output_dir(&dir.join("bom.json"));
+        let missing = check_output_dir(&dir.join("missing").join("bom.json"));
+        let leftovers = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(existing.is_ok());
+        assert_eq!(
+            missing.unwrap_err().to_string(),
+            format!(
+                "Output directory {} does not exist",
+                dir.join("missing").display()
+            )
+        );
+        assert_eq!(leftovers, 0);
+        assert!(check_output_dir(Path::new("/dev/null")).is_ok());
+    }
+
+    #[test]
+    fn test_filter_groups_dev_dependencies() {
+        let lockfile = "GEM
+  remote: https://rubygems.org/
+  specs:
+    debug (1.9.2)
+      irb (~> 1.10)
+    irb (1.14.0)
+    rake (13.0.6)
+
+DEPENDENCIES
+  debug
+  rake
+";
+        let gemfile_groups = groups::parse_groups(
+            "gem \"rake\"\ngroup :development, :test do\n  gem \"debug\"\nend\n",
+        );
+        let lock_file = Path::new("/tmp/project/Gemfile.lock");
+        let names = |specs: &bundler::Gemfile| -> Vec<String> {
+            specs
+                .gems
+                .iter()
+                .map(|gem| gem.get_source().0.to_string())
+                .collect()
+        };
+
+        let mut production = bundler::parse_gemfile(String::from(lockfile));
+        filter_groups(
+            &mut production,
+            Some(&gemfile_groups),
+            &config::parse_test_params(&[]),
+            lock_file,
+        );
+        let mut full = bundler::parse_gemfile(String::from(lockfile));
+        filter_groups(
+            &mut full,
+            Some(&gemfile_groups),
+            &config::parse_test_params(&["--include-dev-dependencies"]),
+            lock_file,
+        );
+        let mut without_gemfile = bundler::parse_gemfile(String::from(lockfile));
+        filter_groups(
+            &mut without_gemfile,
+            None,
+            &config::parse_test_params(&[]),
+            lock_file,
+        );
+
+        assert_eq!(names(&production), ["rake"]);
+        assert_eq!(names(&full), ["debug", "irb", "rake"]);
+        assert_eq!(names(&without_gemfile), ["debug", "irb", "rake"]);
+    }
+
+    #[tokio::test]
+    async fn test_check_not_missing_when_package_absent() {
+        let server = test_support::MockServer::new()
+            .route(
+                "/service/rest/v1/search/assets?name=rails&version=7.1.1&format=rubygems",
+                200,
+                r#"{"items":[],"continuationToken":null}"#,
+            )
+            .start();
+        let gems = vec![gem::Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            ..Default::default()
+        }];
+
+        let nexus = nexus::Nexus::new(
+            server.url().as_str(),
+            nexus::DEFAULT_FORMAT,
+            &client::ClientOptions::default(),
+        )
+        .unwrap();
+        let (result, errors) = repository::check_packages(&gems, &nexus).await;
+        let not_found: Vec<repository::RepoResult> =
+            result.into_iter().filter(|item| item.is_absent()).collect();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            check_not_missing(&not_found, errors.len(), "Nexus")
+                .unwrap_err()
+                .to_string()
+                .as_str(),
+            "1 packages not found in Nexus"
+        );
+        assert!(check_not_missing(&[], 0, "Nexus").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_not_missing_when_repository_fails() {
+        let server = test_support::MockServer::new()
+            .route(
+                "/service/rest/v1/search/assets?name=rails&version=7.1.1&format=rubygems",
+                500,
+                "Internal Server Error",
+            )
+            .start();
+        let gems = vec![gem::Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            ..Default::default()
+        }];
+        let nexus = nexus::Nexus::new(
+            server.url().as_str(),
+            nexus::DEFAULT_FORMAT,
+            &client::ClientOptions {
+                retries: Some(0),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let (result, errors) = repository::check_packages(&gems, &nexus).await;
+
+        assert!(result.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            check_not_missing(&[], errors.len(), "Nexus")
+                .unwrap_err()
+                .to_string(),
+            "1 packages could not be checked in Nexus"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_nexus_repository_without_url() {
+        let server = test_support::MockServer::new().start();
+        let params = config::parse_test_params(&[]);
+        let gems = vec![gem::Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            ..Default::default()
+        }];
+
+        let result = check_repository(&gems, &params).await;
+
+        assert!(result.is_ok());
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_nexus_repository_with_url() {
+        let server = test_support::MockServer::new()
+            .route(
+                "/service/rest/v1/search/assets?name=rails&version=7.1.1&format=rubygems",
+                200,
+                r#"{"items":[],"continuationToken":null}"#,
+            )
+            .start();
+        let url = server.url();
+        let params = config::parse_test_params(&["--nexus-url", url.as_str(), "--fail-on-missing"]);
+        let gems = vec![gem::Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            ..Default::default()
+        }];
+
+        let result = check_repository(&gems, &params).await;
+
+        assert!(result.is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_check_artifactory_repository() {
+        let server = test_support::MockServer::new()
+            .route(
+                "/api/search/artifact?name=rails-7.1.1.gem&repos=gems-local",
+                200,
+                r#"{"results":[]}"#,
+            )
+            .start();
+        let url = server.url();
+        let params = config::parse_test_params(&[
+            "--artifactory-url",
+            url.as_str(),
+            "--artifactory-repo",
+            "gems-local",
+            "--fail-on-missing",
+        ]);
+        let gems = vec![gem::Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            ..Default::default()
+        }];
+
+        let result = check_repository(&gems, &params).await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "1 packages not found in Artifactory"
+        );
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_check_purls_strict() {
+        let gems = vec![gem::Gemspec {
+            purl: String::from("pkg:gem/nokogiri@1.16.0?platform="),
+            ..Default::default()
+        }];
+
+        assert!(check_purls(&gems, false).is_ok());
+        assert_eq!(
+            check_purls(&gems, true).unwrap_err().to_string(),
+            "Malformed purl pkg:gem/nokogiri@1.16.0?platform=: invalid qualifier"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_nexus_repository_writes_report() {
+        let server = test_support::MockServer::new()
+            .route(
+                "/service/rest/v1/search/assets?name=rails&version=7.1.1&format=rubygems",
+                200,
+                r#"{"items":[],"continuationToken":null}"#,
+            )
+            .start();
+        let url = server.url();
+        let report_path = std::env::temp_dir().join("cyclonedx-rs-gem-nexus-report.json");
+        let params = config::parse_test_params(&[
+            "--nexus-url",
+            url.as_str(),
+            "--nexus-report",
+            report_path.to_str().unwrap(),
+        ]);
+        let gems = vec![gem::Gemspec {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            ..Default::default()
+        }];
+
+        let result = check_repository(&gems, &params).await;
+        let report = fs::read_to_string(&report_path).unwrap();
+        fs::remove_file(&report_path).unwrap();
+
+        assert!(result.is_ok());
+        assert!(report.contains(r#""purl": "pkg:gem/rails@7.1.1","#));
+        assert!(report.contains(r#""present": false"#));
+    }
+
+    #[test]
+    fn test_write_bomfile_replaces_regular_file() {
+        let file_name = std::env::temp_dir().join("cyclonedx-rs-gem-write-bom.json");
+        fs::write(&file_name, "previous content, which is longer").unwrap();
+
+        write_bomfile(&file_name, "{}", None, false).unwrap();
+        let content = fs::read_to_string(&file_name).unwrap();
+        fs::remove_file(&file_name).unwrap();
+
+        assert_eq!(content, "{}");
+    }
+
+    #[test]
+    fn test_write_bomfile_binary() {
+        let file_name = std::env::temp_dir().join("cyclonedx-rs-gem-write-bom.bin");
+        let bom: &[u8] = &[0x0a, 0x03, b'1', b'.', b'5', 0xff, 0xfe, 0x00];
+
+        write_bomfile(&file_name, bom, None, false).unwrap();
+        let content = fs::read(&file_name).unwrap();
+        fs::remove_file(&file_name).unwrap();
+
+        assert_eq!(content, bom);
+    }
+
+    #[test]
+    fn test_stream_bomfile() {
+        let file_name = std::env::temp_dir().join("cyclonedx-rs-gem-stream-bom.json");
+        let gems = vec![gem::Gemspec {
+            name: String::from("rake"),
+            version: String::from("13.0.6"),
+            purl: String::from("pkg:gem/rake@13.0.6"),
+            ..Default::default()
+        }];
+        let options = bom_se::SerializeOptions::default();
+
+        stream_bomfile(&file_name, None, |output| {
+            bom_se::serialize_to(&gems, &Format::Json, &options, output)
+        })
+        .unwrap();
+        let content = fs::read_to_string(&file_name).unwrap();
+        fs::remove_file(&file_name).unwrap();
+        let failed = stream_bomfile(&file_name, None, |_| bail!("serialization failed"));
+
+        assert!(content.contains(r#""purl": "pkg:gem/rake@13.0.6""#));
+        assert!(failed.is_err());
+        assert!(!file_name.exists());
+    }
+
+    #[test]
+    fn test_write_bomfile_compressed() {
+        let file_name = std::env::temp_dir().join("cyclonedx-rs-gem-write-bom.json.gz");
+        let bom = r#"{"bomFormat": "CycloneDX", "specVersion": "1.5"}"#;
+
+        write_bomfile(&file_name, bom, None, true).unwrap();
+        let content = fs::read(&file_name).unwrap();
+        fs::remove_file(&file_name).unwrap();
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(content.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+
+        assert_eq!(decompressed, bom);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_bomfile_to_fifo() {
+        let fifo = std::env::temp_dir().join(format!("cyclonedx-rs-gem-{}.fifo", process::id()));
+        let _ = fs::remove_file(&fifo);
+        let status = process::Command::new("mkfifo").arg(&fifo).status().unwrap();
+        assert!(status.success());
+
+        let reader_path = fifo.clone();
+        let reader = std::thread::spawn(move || fs::read_to_string(reader_path).unwrap());
+        let result = write_bomfile(&fifo, "{}", None, false);
+        let content = reader.join().unwrap();
+        let is_fifo = !fs::metadata(&fifo).unwrap().is_file();
+        fs::remove_file(&fifo).unwrap();
+
+        assert!(result.is_ok());
+        assert_eq!(content, "{}");
+        assert!(is_fifo);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_bomfile_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir();
+        let reference = dir.join("cyclonedx-rs-gem-umask-reference");
+        let file_name = dir.join("cyclonedx-rs-gem-umask-bom.json");
+        let _ = fs::remove_file(&file_name);
+        File::create(&reference).unwrap();
+        let mode = |path: &PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+
+        write_bomfile(&file_name, "{}", None, false).unwrap();
+        let umask_mode = mode(&file_name);
+        write_bomfile(&file_name, "{}", Some(0o640), false).unwrap();
+        let explicit_mode = mode(&file_name);
+        write_bomfile(&file_name, "{}", None, false).unwrap();
+        let preserved_mode = mode(&file_name);
+
+        assert_eq!(umask_mode, mode(&reference));
+        assert_eq!(explicit_mode, 0o640);
+        assert_eq!(preserved_mode, 0o640);
+        fs::remove_file(&reference).unwrap();
+        fs::remove_file(&file_name).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_generate_bom() {
+        let server = test_support::MockServer::new()
+            .route(
+                "/api/v1/versions/rake.json",
+                200,
+                r#"[{"authors":"Jim Weirich","number":"13.0.6","platform":"ruby","summary":"Rake","sha":"5ce4bf5037b4196c24ac62834d8db1ce175470391026bd9e557d669beeb19097","licenses":["MIT"]}]"#,
+            )
+            .start();
+        let gemfile = "GEM\n  remote: https://rubygems.org/\n  specs:\n    rake (13.0.6)\n";
+        let options = BomOptions {
+            format: Format::Json,
+            registry_url: server.url(),
+        };
+
+        let bom = generate_bom(gemfile, options).await.unwrap();
+
+        assert!(bom.contains(r#""purl": "pkg:gem/rake@13.0.6""#));
+        assert!(bom.contains(
+            r#""content": "5ce4bf5037b4196c24ac62834d8db1ce175470391026bd9e557d669beeb19097""#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generate_bom_protobuf() {
+        let server = test_support::MockServer::new()
+            .route("/api/v1/versions/rake.json", 200, "[]")
+            .start();
+        let gemfile = "GEM\n  remote: https://rubygems.org/\n  specs:\n    rake (13.0.6)\n";
+        let options = BomOptions {
+            format: Format::Protobuf,
+            registry_url: server.url(),
+        };
+
+        let result = generate_bom(gemfile, options).await;
+
+        assert!(result.is_err());
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_gems_fail_on_empty() {
+        let dir = std::env::temp_dir().join("cyclonedx-rs-gem-collect-empty");
+        fs::create_dir_all(&dir).unwrap();
+        let lock_file = dir.join("Gemfile.lock");
+        fs::write(&lock_file, "PLATFORMS\n  ruby\n").unwrap();
+        let client = client::get_client(&Default::default()).unwrap();
+        let params = config::parse_test_params(&["--fail-on-empty"]);
+
+        let result = collect_gems(
+            &lock_file,
+            &client,
+            &gem::Registry::default(),
+            &cache::Cache::default(),
+            &params,
+        )
+        .await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        let error = result.err().unwrap();
+        assert_eq!(
+            error.downcast_ref::<RunError>().map(RunError::exit_code),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_warmup_delay() {
+        let warmup = Duration::from_millis(1000);
+
+        let delays: Vec<Duration> = (0..6).map(|index| warmup_delay(index, 4, warmup)).collect();
+
+        assert_eq!(
+            delays,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(250),
+                Duration::from_millis(500),
+                Duration::from_millis(750),
+                Duration::ZERO,
+                Duration::ZERO,
+            ]
+        );
+        assert_eq!(warmup_delay(3, 4, Duration::ZERO), Duration::ZERO);
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+static LOGGER: Logger = Logger;
+
+#[derive(Debug, Serialize)]
+struct JsonRecord<'a> {
+    event: &'a str,
+    level: &'a str,
+    message: &'a str,
+}
+
+///
+/// Backend for `log` facade. Log target is used as event name, a short
+/// machine-readable message kind
+///
+struct Logger;
+
+///
+/// Installs logger with given maximum level. In JSON mode every message
+/// is written to stderr as separate JSON object
+///
+pub(crate) fn init(level: LevelFilter, json: bool) {
+    JSON_OUTPUT.store(json, Ordering::Relaxed);
+
+    // logger could be installed only once, repeated calls just change level
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(level);
+}
+
+///
+/// Chooses maximum log level: only errors in quiet mode, all diagnostics
+/// in verbose mode, otherwise level from RUST_LOG or info by default
+///
+pub(crate) fn level(verbose: bool, quiet: bool, env_level: Option<&str>) -> LevelFilter {
+    if quiet {
+        return LevelFilter::Error;
+    }
+    if verbose {
+        return LevelFilter::Debug;
+    }
+
+    env_level
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Info)
+}
+
+impl Log for Logger {
+    // records of dependencies (reqwest, etc) are skipped, their targets
+    // are module paths of other crates
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let target = metadata.target();
+
+        metadata.level() <= log::max_level()
+            && (!target.contains("::") || target.starts_with(env!("CARGO_CRATE_NAME")))
+    }
+
+    //
+    // In plain mode info and debug messages go to stdout as is, warnings
+    // and errors go to stderr with prefix
+    //
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = record.args().to_string();
+        if JSON_OUTPUT.load(Ordering::Relaxed) {
+            eprintln!("{}", format_json(record.target(), record.level(), &message));
+            return;
+        }
+
+        match record.level() {
+            Level::Error => eprintln!("Error: {message}"),
+            Level::Warn => eprintln!("Warning: {message}"),
+            _ => println!("{message}"),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+//
+// Builds single JSON line for diagnostic message. Blank lines, used as
+// separators in plain output, are trimmed
+//
+fn format_json(event: &str, level: Level, message: &str) -> String {
+    let record = JsonRecord {
+        event,
+        level: &level.as_str().to_lowercase(),
+        message: message.trim(),
+    };
+
+    serde_json::to_string(&record).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::FetchPackageError;
+    use serde_json::Value;
+
+    #[test]
+    fn test_fetch_error_json_line() {
+        let error =
+            FetchPackageError::PackageNotFound(String::from("rails"), String::from("7.1.1"));
+
+        let line = format_json("fetch_error", Level::Warn, &error.to_string());
+        let json: Value = serde_json::from_str(&line).unwrap();
+
+        assert!(!line.contains('\n'));
+        assert_eq!(json["event"], "fetch_error");
+        assert_eq!(json["level"], "warn");
+        assert_eq!(json["message"], "Gem not found: rails, version 7.1.1");
+    }
+
+    #[test]
+    fn test_json_line_escapes_message() {
+        let line = format_json("fetch_error", Level::Warn, "Gem \"a\"\nnot found");
+        let json: Value = serde_json::from_str(&line).unwrap();
+
+        assert!(!line.contains('\n'));
+        assert_eq!(json["message"], "Gem \"a\"\nnot found");
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level(false, false, None), LevelFilter::Info);
+        assert_eq!(level(true, false, None), LevelFilter::Debug);
+        assert_eq!(level(false, true, Some("debug")), LevelFilter::Error);
+        assert_eq!(level(false, false, Some("warn")), LevelFilter::Warn);
+        assert_eq!(level(false, false, Some("nonsense")), LevelFilter::Info);
+    }
+
+    #[test]
+    fn test_dependencies_records_skipped() {
+        log::set_max_level(LevelFilter::Debug);
+        let own = Metadata::builder()
+            .level(Level::Debug)
+            .target("gems_received")
+            .build();
+        let dependency = Metadata::builder()
+            .level(Level::Debug)
+            .target("reqwest::connect")
+            .build();
+
+        assert!(LOGGER.enabled(&own));
+        assert!(!LOGGER.enabled(&dependency));
+    }
+}
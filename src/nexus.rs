@@ -1,46 +1,15 @@
-use futures::{stream, StreamExt};
+use async_trait::async_trait;
 use reqwest_middleware::ClientWithMiddleware;
 use serde_json::Value;
-use std::fmt;
 use url::Url;
 
-use crate::client::get_nexus_client;
-use crate::errors::NexusError;
-use crate::gem::Gemspec;
-
-const CONCURRENT_REQUESTS: usize = 3;
-
-type ResultCollection = Vec<Result<NexusResult, NexusError>>;
-
-///
-/// Check packages existance in Nexus repository
-///
-pub(crate) async fn check_packages(
-    packages: &Vec<Gemspec>,
-    nexus_url: &str,
-    verbose: bool,
-) -> Result<Vec<NexusResult>, NexusError> {
-    let nexus = Nexus::new(nexus_url)?;
-
-    let nexus_results = stream::iter(packages)
-        .map(|package| async { nexus.check_package(package).await })
-        .buffer_unordered(CONCURRENT_REQUESTS)
-        .collect::<ResultCollection>()
-        .await;
-
-    let (oks, errors): (ResultCollection, ResultCollection) =
-        nexus_results.into_iter().partition(Result::is_ok);
-    let oks: Vec<NexusResult> = oks.into_iter().map(Result::unwrap).collect();
-
-    if verbose {
-        errors
-            .into_iter()
-            .map(Result::unwrap_err)
-            .for_each(|error| println!("{}", error));
-    }
+use crate::client::{get_nexus_client, ClientOptions};
+use crate::errors::RepoError;
+use crate::gem::{Credentials, Gemspec};
+use crate::repository::{RepoResult, RepositoryChecker};
 
-    Ok(oks)
-}
+const NEXUS: &str = "Nexus";
+pub(crate) const DEFAULT_FORMAT: &str = "rubygems";
 
 pub(crate) struct Nexus {
     // Nexus repository url
@@ -51,73 +20,80 @@ pub(crate) struct Nexus {
 
     // Nexus client instance
     client: ClientWithMiddleware,
-}
 
-#[derive(Debug)]
-pub(crate) struct NexusResult {
-    name: String,
-    version: String,
-    purl: String,
-    is_exist: bool,
+    // Credentials for search API, if Nexus requires authentication
+    credentials: Option<Credentials>,
 }
 
 impl Nexus {
     ///
     /// Initializes new Nexus instance
     ///
-    pub(crate) fn new(repo_url: &str) -> Result<Self, NexusError> {
+    pub(crate) fn new(
+        repo_url: &str,
+        format_artefact: &str,
+        client_options: &ClientOptions,
+    ) -> Result<Self, RepoError> {
         let repo_url =
-            Url::parse(repo_url).map_err(|_| NexusError::UrlParse(repo_url.to_string()))?;
-        let client = get_nexus_client().map_err(|_| NexusError::BuildClient)?;
+            Url::parse(repo_url).map_err(|_| RepoError::UrlParse(NEXUS, repo_url.to_string()))?;
+        let client = get_nexus_client(client_options).map_err(|_| RepoError::BuildClient(NEXUS))?;
 
         Ok(Nexus {
-            format_artefact: String::from("rubygems"),
+            format_artefact: format_artefact.to_string(),
             repo_url,
             client,
+            credentials: None,
         })
     }
 
     ///
-    /// Check package existance in Nexus repository
+    /// Sets credentials, which are sent with every request
     ///
-    pub(crate) async fn check_package(&self, package: &Gemspec) -> Result<NexusResult, NexusError> {
-        let Gemspec { name, version, .. } = &package;
-        let response = self.send_request(name, version).await;
+    pub(crate) fn with_credentials(mut self, credentials: Option<Credentials>) -> Self {
+        self.credentials = credentials;
 
-        let check_result = self.check_response(response);
-
-        check_result.map(|is_exist| NexusResult {
-            name: name.to_string(),
-            version: version.to_string(),
-            purl: package.purl.to_string(),
-            is_exist,
-        })
+        self
     }
 
     //
     // Parses respose json and try to check whether package exists in
     // Nexus repository
     //
-    fn check_response(&self, response: Result<Value, NexusError>) -> Result<bool, NexusError> {
-        response.map(|json| !json["items"].as_array().unwrap().is_empty())
+    fn check_response(
+        &self,
+        name: &str,
+        version: &str,
+        response: Result<Value, RepoError>,
+    ) -> Result<bool, RepoError> {
+        response.and_then(|json| {
+            json["items"]
+                .as_array()
+                .map(|items| !items.is_empty())
+                .ok_or_else(|| {
+                    RepoError::ParseResponse(NEXUS, name.to_string(), version.to_string())
+                })
+        })
     }
 
     //
     // Sends request to Nesus and try to receive response
     //
-    async fn send_request(&self, name: &str, version: &str) -> Result<Value, NexusError> {
+    async fn send_request(&self, name: &str, version: &str) -> Result<Value, RepoError> {
         let url = self.get_search_url(name, version);
-        let response = self
-            .client
-            .get(&url)
+        let request = self.client.get(&url);
+        let request = match &self.credentials {
+            Some(credentials) => credentials.authorize(request),
+            None => request,
+        };
+        let response = request
             .send()
             .await
-            .map_err(|_| NexusError::SendRequest(name.to_string(), version.to_string()))?;
+            .map_err(|_| RepoError::SendRequest(NEXUS, name.to_string(), version.to_string()))?;
 
         let json = response
             .json::<Value>()
             .await
-            .map_err(|_| NexusError::ParseResponse(name.to_string(), version.to_string()))?;
+            .map_err(|_| RepoError::ParseResponse(NEXUS, name.to_string(), version.to_string()))?;
 
         Ok(json)
     }
@@ -139,22 +115,19 @@ impl Nexus {
     }
 }
 
-impl NexusResult {
-    ///
-    /// Check if package absent in NexusResult
-    ///
-    pub(crate) fn is_absent(&self) -> bool {
-        !self.is_exist
+#[async_trait]
+impl RepositoryChecker for Nexus {
+    fn name(&self) -> &'static str {
+        NEXUS
     }
-}
 
-impl fmt::Display for NexusResult {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Package name: {}, version: {}, purl: {}",
-            &self.name, &self.version, &self.purl
-        )
+    async fn check_package(&self, package: &Gemspec) -> Result<RepoResult, RepoError> {
+        let Gemspec { name, version, .. } = &package;
+        let response = self.send_request(name, version).await;
+
+        let check_result = self.check_response(name, version, response);
+
+        check_result.map(|is_exist| RepoResult::new(package, is_exist))
     }
 }
 
@@ -164,7 +137,12 @@ mod tests {
 
     #[test]
     fn test_build_search_url() {
-        let nexus = Nexus::new("https://mynexus.com").unwrap();
+        let nexus = Nexus::new(
+            "https://mynexus.com",
+            DEFAULT_FORMAT,
+            &ClientOptions::default(),
+        )
+        .unwrap();
         let name = "rails";
         let version = "7.1.1";
 
@@ -173,14 +151,34 @@ mod tests {
         assert_eq!(url, String::from("https://mynexus.com/service/rest/v1/search/assets?name=rails&version=7.1.1&format=rubygems"))
     }
 
+    #[test]
+    fn test_build_search_url_with_custom_format() {
+        let nexus = Nexus::new(
+            "https://mynexus.com",
+            "gems-mirror",
+            &ClientOptions::default(),
+        )
+        .unwrap();
+
+        let url = nexus.get_search_url("rails", "7.1.1");
+
+        assert_eq!(url, String::from("https://mynexus.com/service/rest/v1/search/assets?name=rails&version=7.1.1&format=gems-mirror"))
+    }
+
     #[test]
     fn test_when_request_fail() {
-        let nexus = Nexus::new("https://mynexus.com").unwrap();
+        let nexus = Nexus::new(
+            "https://mynexus.com",
+            DEFAULT_FORMAT,
+            &ClientOptions::default(),
+        )
+        .unwrap();
         let name = "rails";
         let version = "7.1.1";
-        let respose: NexusError = NexusError::SendRequest(name.to_string(), version.to_string());
+        let respose: RepoError =
+            RepoError::SendRequest(NEXUS, name.to_string(), version.to_string());
 
-        let result = nexus.check_response(Err(respose));
+        let result = nexus.check_response(name, version, Err(respose));
 
         assert!(result.is_err());
         assert_eq!(
@@ -191,16 +189,21 @@ mod tests {
 
     #[test]
     fn test_when_request_success_empty_items() {
-        let nexus = Nexus::new("https://mynexus.com").unwrap();
+        let nexus = Nexus::new(
+            "https://mynexus.com",
+            DEFAULT_FORMAT,
+            &ClientOptions::default(),
+        )
+        .unwrap();
         let name = "rails";
         let version = "7.1.1";
         let response_content = r#"{
   "continuationToken": null,
   "items": []
 }"#;
-        let respose: Result<Value, NexusError> = serde_json::from_str(response_content)
-            .map_err(|_| NexusError::ParseResponse(name.to_string(), version.to_string()));
-        let result = nexus.check_response(respose);
+        let respose: Result<Value, RepoError> = serde_json::from_str(response_content)
+            .map_err(|_| RepoError::ParseResponse(NEXUS, name.to_string(), version.to_string()));
+        let result = nexus.check_response(name, version, respose);
 
         assert!(result.is_ok());
         assert!(!result.unwrap());
@@ -208,7 +211,12 @@ mod tests {
 
     #[test]
     fn test_when_request_success_not_empty_items() {
-        let nexus = Nexus::new("https://mynexus.com").unwrap();
+        let nexus = Nexus::new(
+            "https://mynexus.com",
+            DEFAULT_FORMAT,
+            &ClientOptions::default(),
+        )
+        .unwrap();
         let name = "rails";
         let version = "7.1.1";
         let response_content = r#"{
@@ -255,35 +263,58 @@ mod tests {
     }
   ]
 }"#;
-        let respose: Result<Value, NexusError> = serde_json::from_str(response_content)
-            .map_err(|_| NexusError::ParseResponse(name.to_string(), version.to_string()));
-        let result = nexus.check_response(respose);
+        let respose: Result<Value, RepoError> = serde_json::from_str(response_content)
+            .map_err(|_| RepoError::ParseResponse(NEXUS, name.to_string(), version.to_string()));
+        let result = nexus.check_response(name, version, respose);
 
         assert!(result.is_ok());
         assert!(result.unwrap());
     }
 
     #[test]
-    fn test_package_exists_in_nexus() {
-        let package: NexusResult = NexusResult {
-            name: String::from("rails"),
-            version: String::from("7.1.1"),
-            purl: String::from("pkg:gem/rails@7.1.1"),
-            is_exist: true,
-        };
+    fn test_when_response_without_items() {
+        let nexus = Nexus::new(
+            "https://mynexus.com",
+            DEFAULT_FORMAT,
+            &ClientOptions::default(),
+        )
+        .unwrap();
+        let response: Value = serde_json::from_str(r#"{"error":"unauthorized"}"#).unwrap();
+
+        let result = nexus.check_response("rails", "7.1.1", Ok(response));
 
-        assert!(!package.is_absent());
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string().as_str(),
+            "Could not parse Nexus response for gem rails version 7.1.1"
+        );
     }
 
-    #[test]
-    fn test_package_not_exists_in_nexus() {
-        let package: NexusResult = NexusResult {
-            name: String::from("rails"),
-            version: String::from("7.1.1"),
-            purl: String::from("pkg:gem/rails@7.1.1"),
-            is_exist: false,
-        };
+    #[tokio::test]
+    async fn test_credentials_sent() {
+        let server = crate::test_support::MockServer::new()
+            .route(
+                "/service/rest/v1/search/assets?name=rails&version=7.1.1&format=rubygems",
+                200,
+                r#"{"items":[]}"#,
+            )
+            .start();
+        let nexus = Nexus::new(
+            server.url().as_str(),
+            DEFAULT_FORMAT,
+            &ClientOptions::default(),
+        )
+        .unwrap()
+        .with_credentials(Some(Credentials::Basic(
+            String::from("ci"),
+            Some(String::from("secret")),
+        )));
 
-        assert!(package.is_absent());
+        let result = nexus.send_request("rails", "7.1.1").await;
+        let request = server.requests()[0].to_lowercase();
+
+        assert!(result.is_ok());
+        // base64 of ci:secret
+        assert!(request.contains("authorization: basic y2k6c2vjcmv0\r\n"));
     }
 }
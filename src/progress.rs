@@ -0,0 +1,178 @@
+use clap::ValueEnum;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug, Default)]
+pub(crate) enum ProgressMode {
+    // show progress bar only when stdout is a terminal and not in quiet mode
+    #[default]
+    Auto,
+
+    // always show progress bar
+    Always,
+
+    // never show progress bar
+    Never,
+}
+
+impl ProgressMode {
+    ///
+    /// Decides whether progress bar should be drawn
+    ///
+    pub(crate) fn is_enabled(&self, quiet: bool) -> bool {
+        match self {
+            ProgressMode::Auto => !quiet && io::stdout().is_terminal(),
+            ProgressMode::Always => true,
+            ProgressMode::Never => false,
+        }
+    }
+}
+
+const BAR_WIDTH: usize = 30;
+
+struct State {
+    resolved: usize,
+    target: Box<dyn Write + Send>,
+}
+
+///
+/// Progress bar for fetching gems info, drawn to stderr as
+/// `[#####     ] resolved/total` line redrawn in place
+///
+pub(crate) struct Progress {
+    total: usize,
+    state: Option<Mutex<State>>,
+}
+
+impl Progress {
+    pub(crate) fn new(total: usize, enabled: bool) -> Self {
+        Self::with_target(total, enabled.then(|| Box::new(io::stderr()) as _))
+    }
+
+    fn with_target(total: usize, target: Option<Box<dyn Write + Send>>) -> Self {
+        let progress = Progress {
+            total,
+            state: target.map(|target| {
+                Mutex::new(State {
+                    resolved: 0,
+                    target,
+                })
+            }),
+        };
+        progress.draw(|_| {});
+
+        progress
+    }
+
+    ///
+    /// Advances progress bar by one resolved gem
+    ///
+    pub(crate) fn inc(&self) {
+        self.draw(|state| state.resolved += 1);
+    }
+
+    ///
+    /// Clears progress bar line, so following output starts from
+    /// the beginning of line
+    ///
+    pub(crate) fn finish(&self) {
+        if let Some(state) = &self.state {
+            let mut state = state.lock().unwrap();
+            let _ = write!(state.target, "\r\x1b[2K");
+            let _ = state.target.flush();
+        }
+    }
+
+    fn draw(&self, update: impl FnOnce(&mut State)) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let mut state = state.lock().unwrap();
+        update(&mut state);
+        let line = render(state.resolved, self.total);
+        let _ = write!(state.target, "\r{line}");
+        let _ = state.target.flush();
+    }
+}
+
+//
+// Renders progress bar line for given resolved and total counts
+//
+fn render(resolved: usize, total: usize) -> String {
+    let filled = (BAR_WIDTH * resolved.min(total))
+        .checked_div(total)
+        .unwrap_or(BAR_WIDTH);
+
+    format!(
+        "[{}{}] {resolved}/{total}",
+        "#".repeat(filled),
+        " ".repeat(BAR_WIDTH - filled)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(render(0, 3), format!("[{}] 0/3", " ".repeat(30)));
+        assert_eq!(
+            render(1, 3),
+            format!("[{}{}] 1/3", "#".repeat(10), " ".repeat(20))
+        );
+        assert_eq!(render(3, 3), format!("[{}] 3/3", "#".repeat(30)));
+        assert_eq!(render(0, 0), format!("[{}] 0/0", "#".repeat(30)));
+    }
+
+    #[test]
+    fn test_progress() {
+        let buffer = Buffer::default();
+        let progress = Progress::with_target(2, Some(Box::new(buffer.clone())));
+        progress.inc();
+        progress.inc();
+        progress.finish();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            output,
+            format!(
+                "\r{}\r{}\r{}\r\x1b[2K",
+                render(0, 2),
+                render(1, 2),
+                render(2, 2)
+            )
+        );
+    }
+
+    #[test]
+    fn test_progress_disabled() {
+        let progress = Progress::new(2, false);
+        progress.inc();
+        progress.finish();
+
+        assert!(progress.state.is_none());
+    }
+
+    #[test]
+    fn test_progress_mode() {
+        assert!(ProgressMode::Always.is_enabled(true));
+        assert!(!ProgressMode::Never.is_enabled(false));
+        assert!(!ProgressMode::Auto.is_enabled(true));
+    }
+}
@@ -0,0 +1,242 @@
+use crate::errors::PurlError;
+use crate::gem::Gemspec;
+
+const SCHEME: &str = "pkg:";
+const GEM_TYPE: &str = "gem";
+
+///
+/// Builds gem purl. Qualifiers with empty values are skipped, others are
+/// sorted by key and their values are percent encoded
+///
+pub(crate) fn build_gem_purl(name: &str, version: &str, qualifiers: &[(&str, &str)]) -> String {
+    let purl = format!("{SCHEME}{GEM_TYPE}/{name}@{version}");
+
+    let mut qualifiers: Vec<&(&str, &str)> = qualifiers
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .collect();
+    if qualifiers.is_empty() {
+        return purl;
+    }
+    qualifiers.sort_by_key(|(key, _)| *key);
+
+    let qualifiers: Vec<String> = qualifiers
+        .iter()
+        .map(|(key, value)| format!("{key}={}", percent_encode(value)))
+        .collect();
+
+    format!("{purl}?{}", qualifiers.join("&"))
+}
+
+///
+/// Checks generated purls against package url specification. Returns
+/// errors for all malformed purls
+///
+pub(crate) fn find_invalid(gems: &[Gemspec]) -> Vec<PurlError> {
+    gems.iter()
+        .filter_map(|gem| validate(&gem.purl).err())
+        .collect()
+}
+
+///
+/// Parses purl in `pkg:type/namespace/name@version?qualifiers#subpath`
+/// form and checks its components
+///
+pub(crate) fn validate(purl: &str) -> Result<(), PurlError> {
+    let error = |reason| Err(PurlError::Malformed(purl.to_string(), reason));
+
+    let Some(rest) = purl.strip_prefix(SCHEME) else {
+        return error("scheme must be pkg");
+    };
+    if !is_percent_encoded(rest) {
+        return error("invalid percent encoding");
+    }
+
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (rest, qualifiers) = match rest.split_once('?') {
+        Some((rest, qualifiers)) => (rest, Some(qualifiers)),
+        None => (rest, None),
+    };
+    let (rest, version) = match rest.rsplit_once('@') {
+        Some((rest, version)) => (rest, Some(version)),
+        None => (rest, None),
+    };
+
+    let Some((package_type, path)) = rest.split_once('/') else {
+        return error("type and name are required");
+    };
+    if !is_valid_type(package_type) {
+        return error("invalid type");
+    }
+    if path.rsplit('/').next().unwrap_or_default().is_empty() {
+        return error("name is required");
+    }
+    if version.is_some_and(str::is_empty) {
+        return error("version must not be empty");
+    }
+    if let Some(qualifiers) = qualifiers {
+        if !are_valid_qualifiers(qualifiers) {
+            return error("invalid qualifier");
+        }
+    }
+
+    Ok(())
+}
+
+//
+// Type is composed of ASCII letters, digits, '.', '+' and '-' and
+// must not start with digit
+//
+fn is_valid_type(package_type: &str) -> bool {
+    package_type
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic())
+        && package_type
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || ".+-".contains(char))
+}
+
+//
+// Qualifiers are `key=value` pairs separated by '&'. Keys are unique
+// lowercase ASCII letters, digits, '.', '-' and '_', values are not empty
+//
+fn are_valid_qualifiers(qualifiers: &str) -> bool {
+    let mut keys = Vec::new();
+
+    qualifiers.split('&').all(|pair| {
+        let Some((key, value)) = pair.split_once('=') else {
+            return false;
+        };
+        let is_valid_key = key
+            .chars()
+            .next()
+            .is_some_and(|first| !first.is_ascii_digit())
+            && key.chars().all(|char| {
+                char.is_ascii_lowercase() || char.is_ascii_digit() || ".-_".contains(char)
+            });
+        let is_unique = !keys.contains(&key);
+        keys.push(key);
+
+        is_valid_key && is_unique && !value.is_empty()
+    })
+}
+
+//
+// Encodes all characters except unreserved ones and ':', which purl
+// specification allows to keep as is
+//
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || b"-._~:".contains(&byte) {
+                char::from(byte).to_string()
+            } else {
+                format!("%{byte:02X}")
+            }
+        })
+        .collect()
+}
+
+//
+// Every '%' must be followed by two hex digits
+//
+fn is_percent_encoded(value: &str) -> bool {
+    let bytes = value.as_bytes();
+
+    bytes.iter().enumerate().all(|(index, byte)| {
+        *byte != b'%'
+            || bytes
+                .get(index + 1..index + 3)
+                .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_purls() {
+        assert!(validate("pkg:gem/rails@7.1.1").is_ok());
+        assert!(validate("pkg:gem/nokogiri@1.16.0?platform=x86_64-linux").is_ok());
+        assert!(validate("pkg:gem/rails").is_ok());
+        assert!(validate("pkg:gem/name%20with%20space@1.0.0#lib/file.rb").is_ok());
+    }
+
+    #[test]
+    fn test_build_gem_purl() {
+        let purl = build_gem_purl(
+            "nokogiri",
+            "1.16.4",
+            &[
+                ("repository_url", "https://gems.example.com/private"),
+                ("platform", "x86_64-linux"),
+                ("checksum", "sha256:5ce4"),
+            ],
+        );
+
+        assert_eq!(
+            purl,
+            "pkg:gem/nokogiri@1.16.4?checksum=sha256:5ce4&platform=x86_64-linux&repository_url=https:%2F%2Fgems.example.com%2Fprivate"
+        );
+        assert!(validate(&purl).is_ok());
+        assert_eq!(
+            build_gem_purl("rake", "13.0.6", &[("platform", "")]),
+            "pkg:gem/rake@13.0.6"
+        );
+    }
+
+    #[test]
+    fn test_malformed_purls() {
+        let cases = [
+            ("gem/rails@7.1.1", "scheme must be pkg"),
+            ("pkg:rails", "type and name are required"),
+            ("pkg:1gem/rails@7.1.1", "invalid type"),
+            ("pkg:gem/@7.1.1", "name is required"),
+            ("pkg:gem/rails@", "version must not be empty"),
+            ("pkg:gem/rails@7.1.1%2", "invalid percent encoding"),
+        ];
+
+        for (purl, reason) in cases {
+            assert_eq!(
+                validate(purl).unwrap_err().to_string(),
+                format!("Malformed purl {purl}: {reason}")
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_qualifier() {
+        for purl in [
+            "pkg:gem/nokogiri@1.16.0?platform=",
+            "pkg:gem/nokogiri@1.16.0?Platform=x86_64-linux",
+            "pkg:gem/nokogiri@1.16.0?platform",
+            "pkg:gem/nokogiri@1.16.0?platform=java&platform=ruby",
+        ] {
+            assert_eq!(
+                validate(purl).unwrap_err().to_string(),
+                format!("Malformed purl {purl}: invalid qualifier")
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_invalid() {
+        let gems = vec![
+            Gemspec {
+                purl: String::from("pkg:gem/rails@7.1.1"),
+                ..Default::default()
+            },
+            Gemspec {
+                purl: String::from("pkg:gem/nokogiri@1.16.0?platform="),
+                ..Default::default()
+            },
+        ];
+
+        let result = find_invalid(&gems);
+
+        assert_eq!(result.len(), 1);
+    }
+}
@@ -0,0 +1,238 @@
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use std::fmt;
+
+use crate::errors::RepoError;
+use crate::gem::Gemspec;
+
+const CONCURRENT_REQUESTS: usize = 3;
+
+type ResultCollection = Vec<Result<RepoResult, RepoError>>;
+
+///
+/// Packages repository, where gems presence is checked
+///
+#[async_trait]
+pub(crate) trait RepositoryChecker: Sync {
+    ///
+    /// Returns repository kind for messages
+    ///
+    fn name(&self) -> &'static str;
+
+    ///
+    /// Check package existance in repository
+    ///
+    async fn check_package(&self, package: &Gemspec) -> Result<RepoResult, RepoError>;
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct RepoResult {
+    name: String,
+    version: String,
+    purl: String,
+    #[serde(rename = "present")]
+    is_exist: bool,
+}
+
+///
+/// Check packages existance in configured repository. Returns check
+/// results and errors of packages, which could not be checked
+///
+pub(crate) async fn check_packages<C>(
+    packages: &[Gemspec],
+    checker: &C,
+) -> (Vec<RepoResult>, Vec<RepoError>)
+where
+    C: RepositoryChecker + ?Sized,
+{
+    let results = stream::iter(packages)
+        .map(|package| checker.check_package(package))
+        .buffer_unordered(CONCURRENT_REQUESTS)
+        .collect::<ResultCollection>()
+        .await;
+
+    let (oks, errors): (ResultCollection, ResultCollection) =
+        results.into_iter().partition(Result::is_ok);
+
+    let errors: Vec<RepoError> = errors.into_iter().map(Result::unwrap_err).collect();
+    errors
+        .iter()
+        .for_each(|error| log::warn!(target: "nexus_error", "{}", error));
+
+    (oks.into_iter().map(Result::unwrap).collect(), errors)
+}
+
+///
+/// Serializes repository check results to json report. Present packages
+/// are included only if `include_present` is set
+///
+pub(crate) fn build_report(
+    results: &[RepoResult],
+    include_present: bool,
+) -> Result<String, serde_json::Error> {
+    let packages: Vec<&RepoResult> = results
+        .iter()
+        .filter(|result| include_present || result.is_absent())
+        .collect();
+
+    serde_json::to_string_pretty(&packages)
+}
+
+impl RepoResult {
+    ///
+    /// Builds check result for package
+    ///
+    pub(crate) fn new(package: &Gemspec, is_exist: bool) -> Self {
+        RepoResult {
+            name: package.name.to_string(),
+            version: package.version.to_string(),
+            purl: package.purl.to_string(),
+            is_exist,
+        }
+    }
+
+    ///
+    /// Check if package absent in repository
+    ///
+    pub(crate) fn is_absent(&self) -> bool {
+        !self.is_exist
+    }
+}
+
+impl fmt::Display for RepoResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Package name: {}, version: {}, purl: {}",
+            &self.name, &self.version, &self.purl
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Repository, which contains only given gems names and fails for
+    // "broken" gem
+    struct FakeChecker {
+        present: Vec<&'static str>,
+    }
+
+    #[async_trait]
+    impl RepositoryChecker for FakeChecker {
+        fn name(&self) -> &'static str {
+            "Fake"
+        }
+
+        async fn check_package(&self, package: &Gemspec) -> Result<RepoResult, RepoError> {
+            if package.name == "broken" {
+                return Err(RepoError::SendRequest(
+                    self.name(),
+                    package.name.clone(),
+                    package.version.clone(),
+                ));
+            }
+
+            Ok(RepoResult::new(
+                package,
+                self.present.contains(&package.name.as_str()),
+            ))
+        }
+    }
+
+    fn build_gem(name: &str) -> Gemspec {
+        Gemspec {
+            name: name.to_string(),
+            version: String::from("1.0.0"),
+            purl: format!("pkg:gem/{name}@1.0.0"),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_packages() {
+        let checker = FakeChecker {
+            present: vec!["rails"],
+        };
+        let gems = vec![build_gem("rails"), build_gem("rack"), build_gem("broken")];
+
+        let (mut results, errors) = check_packages(&gems, &checker).await;
+        results.sort_by(|left, right| left.name.cmp(&right.name));
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "rack");
+        assert!(results[0].is_absent());
+        assert_eq!(results[1].name, "rails");
+        assert!(!results[1].is_absent());
+    }
+
+    #[test]
+    fn test_package_exists_in_repository() {
+        let package: RepoResult = RepoResult {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            is_exist: true,
+        };
+
+        assert!(!package.is_absent());
+    }
+
+    #[test]
+    fn test_package_not_exists_in_repository() {
+        let package: RepoResult = RepoResult {
+            name: String::from("rails"),
+            version: String::from("7.1.1"),
+            purl: String::from("pkg:gem/rails@7.1.1"),
+            is_exist: false,
+        };
+
+        assert!(package.is_absent());
+    }
+
+    #[test]
+    fn test_build_report() {
+        let results = vec![
+            RepoResult {
+                name: String::from("rails"),
+                version: String::from("7.1.1"),
+                purl: String::from("pkg:gem/rails@7.1.1"),
+                is_exist: true,
+            },
+            RepoResult {
+                name: String::from("rack"),
+                version: String::from("3.0.0"),
+                purl: String::from("pkg:gem/rack@3.0.0"),
+                is_exist: false,
+            },
+        ];
+        let missing = r#"  {
+    "name": "rack",
+    "version": "3.0.0",
+    "purl": "pkg:gem/rack@3.0.0",
+    "present": false
+  }"#;
+
+        assert_eq!(
+            build_report(&results, false).unwrap(),
+            format!("[\n{missing}\n]")
+        );
+        assert_eq!(
+            build_report(&results, true).unwrap(),
+            format!(
+                r#"[
+  {{
+    "name": "rails",
+    "version": "7.1.1",
+    "purl": "pkg:gem/rails@7.1.1",
+    "present": true
+  }},
+{missing}
+]"#
+            )
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use crate::gem::{Gemspec, Property};
+use crate::timestamp;
+
+pub(crate) const STALE_PROPERTY: &str = "cdx:rubygems:stale";
+
+///
+/// Parses maximum gem age like `90d`, `12w`, `6m` or `2y` into number
+/// of days. Months and years are counted as 30 and 365 days
+///
+pub(crate) fn parse_max_age(value: &str) -> Result<i64, String> {
+    let error = || format!("Incorrect duration {value}, expected number with d, w, m or y suffix");
+
+    let value = value.trim();
+    let unit = value.chars().last().ok_or_else(error)?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| error())?;
+
+    let multiplier = match unit {
+        'd' => 1,
+        'w' => 7,
+        'm' => 30,
+        'y' => 365,
+        _ => return Err(error()),
+    };
+
+    Ok(amount * multiplier)
+}
+
+///
+/// Marks gems, which pinned version was released more than `max_age` days
+/// before `today`, with `cdx:rubygems:stale` property. Returns list of
+/// stale gems in `name version` form. Gems without release date are skipped
+///
+pub(crate) fn mark_stale(gems: &mut [Gemspec], max_age: i64, today: i64) -> Vec<String> {
+    gems.iter_mut()
+        .filter(|gem| is_stale(gem, max_age, today))
+        .map(|gem| {
+            gem.properties
+                .push(Property::new(STALE_PROPERTY, String::from("true")));
+
+            format!("{} {}", gem.name, gem.version)
+        })
+        .collect()
+}
+
+//
+// Checks if gem release date is older than allowed
+//
+fn is_stale(gem: &Gemspec, max_age: i64, today: i64) -> bool {
+    gem.released
+        .as_deref()
+        .and_then(timestamp::parse_days)
+        .is_some_and(|released| today - released > max_age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_gem(name: &str, released: Option<&str>) -> Gemspec {
+        Gemspec {
+            name: String::from(name),
+            version: String::from("1.0.0"),
+            released: released.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_max_age() {
+        assert_eq!(parse_max_age("90d"), Ok(90));
+        assert_eq!(parse_max_age("2w"), Ok(14));
+        assert_eq!(parse_max_age("6m"), Ok(180));
+        assert_eq!(parse_max_age("1y"), Ok(365));
+    }
+
+    #[test]
+    fn test_parse_max_age_invalid() {
+        assert!(parse_max_age("").is_err());
+        assert!(parse_max_age("90").is_err());
+        assert!(parse_max_age("d").is_err());
+        assert!(parse_max_age("ninety days").is_err());
+    }
+
+    #[test]
+    fn test_mark_stale() {
+        // 2024-10-16
+        let today = 20012;
+        let mut gems = vec![
+            build_gem("old", Some("2019-03-13T16:39:08.000Z")),
+            build_gem("fresh", Some("2024-09-30T10:00:00.000Z")),
+            build_gem("unknown", None),
+        ];
+
+        let stale = mark_stale(&mut gems, 365, today);
+
+        assert_eq!(stale, vec![String::from("old 1.0.0")]);
+        assert_eq!(
+            gems[0].properties,
+            vec![Property::new(STALE_PROPERTY, String::from("true"))]
+        );
+        assert!(gems[1].properties.is_empty());
+        assert!(gems[2].properties.is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use url::Url;
+
+///
+/// Fake http server for tests of networked code. Every request path is
+/// mapped to canned status and body, unknown paths get 404. All received
+/// requests are recorded and could be inspected after the call
+///
+pub(crate) struct MockServer {
+    routes: HashMap<String, Vec<Route>>,
+}
+
+#[derive(Clone)]
+struct Route {
+    status: u16,
+    headers: String,
+    body: String,
+}
+
+pub(crate) struct RunningServer {
+    url: Url,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub(crate) fn new() -> Self {
+        Self {
+            routes: HashMap::new(),
+        }
+    }
+
+    ///
+    /// Responds to requests with given path (including query string)
+    /// with given status and body. Repeated routes for the same path are
+    /// used in turn for subsequent requests, the last one is kept
+    ///
+    pub(crate) fn route(self, path: &str, status: u16, body: &str) -> Self {
+        self.route_with_headers(path, status, &[], body)
+    }
+
+    ///
+    /// Same as `route`, but response also contains given headers
+    ///
+    pub(crate) fn route_with_headers(
+        mut self,
+        path: &str,
+        status: u16,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Self {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}\r\n"))
+            .collect();
+        self.routes
+            .entry(String::from(path))
+            .or_default()
+            .push(Route {
+                status,
+                headers,
+                body: String::from(body),
+            });
+
+        self
+    }
+
+    ///
+    /// Starts serving requests in background thread on random local port
+    ///
+    pub(crate) fn start(self) -> RunningServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                self.respond(stream, &recorded);
+            }
+        });
+
+        RunningServer { url, requests }
+    }
+
+    //
+    // Reads request head, records it and writes canned response
+    //
+    fn respond(&self, mut stream: TcpStream, recorded: &Mutex<Vec<String>>) {
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+
+        while !request.ends_with(b"\r\n\r\n") {
+            match stream.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(size) => request.extend_from_slice(&buffer[..size]),
+            }
+        }
+
+        let request = String::from_utf8_lossy(&request).into_owned();
+        let path = request.split_whitespace().nth(1).unwrap_or_default();
+        let mut recorded = recorded.lock().unwrap();
+        let served = recorded
+            .iter()
+            .filter(|previous| previous.split_whitespace().nth(1) == Some(path))
+            .count();
+        let route = self
+            .routes
+            .get(path)
+            .and_then(|responses| responses.get(served).or(responses.last()))
+            .cloned()
+            .unwrap_or(Route {
+                status: 404,
+                headers: String::new(),
+                body: String::new(),
+            });
+
+        recorded.push(request);
+        drop(recorded);
+
+        let response = format!(
+            "HTTP/1.1 {} Mock\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            route.status,
+            route.headers,
+            route.body.len(),
+            route.body
+        );
+        let _ = stream.write_all(response.as_bytes());
+    }
+}
+
+impl RunningServer {
+    pub(crate) fn url(&self) -> Url {
+        self.url.clone()
+    }
+
+    ///
+    /// Returns all received requests heads in order of arrival
+    ///
+    pub(crate) fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
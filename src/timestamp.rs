@@ -0,0 +1,240 @@
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+///
+/// Returns current date as number of days since unix epoch
+///
+pub(crate) fn today() -> i64 {
+    (now_seconds() / SECONDS_PER_DAY) as i64
+}
+
+///
+/// Returns bom creation time in RFC 3339 format. Explicitly given unix
+/// timestamp is used first, then `SOURCE_DATE_EPOCH` environment variable
+/// for reproducible builds, current time otherwise
+///
+pub(crate) fn bom_timestamp(timestamp: Option<u64>) -> Result<String> {
+    let seconds = match (timestamp, env::var(SOURCE_DATE_EPOCH).ok()) {
+        (Some(seconds), _) => seconds,
+        (None, Some(epoch)) => parse_epoch(&epoch)
+            .with_context(|| format!("Incorrect {SOURCE_DATE_EPOCH} value {epoch}"))?,
+        (None, None) => now_seconds(),
+    };
+
+    Ok(format_rfc3339(seconds))
+}
+
+//
+// Parses `SOURCE_DATE_EPOCH` value, which is number of seconds since unix epoch
+//
+fn parse_epoch(value: &str) -> Option<u64> {
+    value.trim().parse().ok()
+}
+
+///
+/// Parses RFC 3339 date and time like `2023-10-11T19:31:51Z` or
+/// `2023-10-11T22:31:51.083+03:00` into number of seconds since unix epoch.
+/// Fractional seconds are ignored
+///
+pub(crate) fn parse_rfc3339(value: &str) -> Result<u64, String> {
+    let error = || format!("Incorrect timestamp {value}, expected RFC 3339 date and time");
+
+    let days = parse_days(value).ok_or_else(error)?;
+    let time = value
+        .get(10..)
+        .and_then(|time| time.strip_prefix(['T', 't', ' ']))
+        .ok_or_else(error)?;
+    let (clock, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(index) => time.split_at(index),
+        None => return Err(error()),
+    };
+    let clock = clock.split_once('.').map_or(clock, |(clock, _)| clock);
+
+    let seconds = parse_clock(clock, 23).ok_or_else(error)?;
+    let offset = match offset {
+        "Z" | "z" => 0,
+        _ => {
+            let seconds = parse_clock(&offset[1..], 0).ok_or_else(error)?;
+            if offset.starts_with('-') {
+                -seconds
+            } else {
+                seconds
+            }
+        }
+    };
+
+    u64::try_from(days * SECONDS_PER_DAY as i64 + seconds - offset).map_err(|_| error())
+}
+
+//
+// Parses `HH:MM:SS` or `HH:MM` into number of seconds. Hours above
+// `max_hours` are allowed only for time zone offsets
+//
+fn parse_clock(value: &str, max_hours: i64) -> Option<i64> {
+    let parts: Vec<i64> = value
+        .split(':')
+        .map(|part| (part.len() == 2).then(|| part.parse().ok()).flatten())
+        .collect::<Option<_>>()?;
+
+    let (hours, minutes, seconds) = match parts[..] {
+        [hours, minutes, seconds] if max_hours > 0 => (hours, minutes, seconds),
+        [hours, minutes] if max_hours == 0 => (hours, minutes, 0),
+        _ => return None,
+    };
+    if (max_hours > 0 && hours > max_hours) || minutes > 59 || seconds > 60 {
+        return None;
+    }
+
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+//
+// Returns number of seconds since unix epoch
+//
+fn now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+//
+// Formats unix timestamp as RFC 3339 UTC date and time
+//
+fn format_rfc3339(seconds: u64) -> String {
+    let (year, month, day) = civil_from_days((seconds / SECONDS_PER_DAY) as i64);
+    let time = seconds % SECONDS_PER_DAY;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+///
+/// Parses date from rubygems timestamp like `2023-10-11T19:31:51.083Z`
+/// and returns number of days since unix epoch. Time part is ignored
+///
+pub(crate) fn parse_days(value: &str) -> Option<i64> {
+    let date = value.get(0..10)?;
+    let mut parts = date.splitn(3, '-');
+
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day))
+}
+
+//
+// Converts gregorian calendar date to number of days since unix epoch
+// (Howard Hinnant's days_from_civil algorithm)
+//
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+//
+// Converts number of days since unix epoch to gregorian calendar date
+// (Howard Hinnant's civil_from_days algorithm)
+//
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_days_epoch() {
+        assert_eq!(parse_days("1970-01-01T00:00:00.000Z"), Some(0));
+    }
+
+    #[test]
+    fn test_parse_days_rubygems_timestamp() {
+        assert_eq!(parse_days("2023-10-11T19:31:51.083Z"), Some(19641));
+        assert_eq!(parse_days("2024-03-01"), Some(19783));
+    }
+
+    #[test]
+    fn test_parse_days_invalid() {
+        assert_eq!(parse_days(""), None);
+        assert_eq!(parse_days("yesterday"), None);
+        assert_eq!(parse_days("2023-13-11T19:31:51.083Z"), None);
+    }
+
+    #[test]
+    fn test_fixed_epoch() {
+        assert_eq!(
+            parse_epoch("1697052711").map(format_rfc3339).as_deref(),
+            Some("2023-10-11T19:31:51Z")
+        );
+        assert_eq!(parse_epoch("yesterday"), None);
+    }
+
+    #[test]
+    fn test_explicit_timestamp() {
+        assert_eq!(
+            bom_timestamp(Some(1_697_052_711)).unwrap(),
+            "2023-10-11T19:31:51Z"
+        );
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("2023-10-11T19:31:51Z"), Ok(1_697_052_711));
+        assert_eq!(
+            parse_rfc3339("2023-10-11T22:31:51.083+03:00"),
+            Ok(1_697_052_711)
+        );
+        assert_eq!(
+            parse_rfc3339("2023-10-11t17:01:51-02:30"),
+            Ok(1_697_052_711)
+        );
+        assert!(parse_rfc3339("2023-10-11").is_err());
+        assert!(parse_rfc3339("2023-10-11T19:31:51").is_err());
+        assert!(parse_rfc3339("2023-10-11T25:31:51Z").is_err());
+        assert!(parse_rfc3339("1960-10-11T19:31:51Z").is_err());
+    }
+
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_rfc3339(1_697_052_711), "2023-10-11T19:31:51Z");
+        assert_eq!(format_rfc3339(1_709_251_199), "2024-02-29T23:59:59Z");
+    }
+}